@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput, BenchmarkId};
-use wfc::wfc::{rules, vec3d::Vec3D, baseline, traits::WFC, queueprop, queueprop_bitarrayset, queueprop_bitarrayset_fibheap, stackprop};
+use wfc::wfc::{rules, vec3d::Vec3D, baseline, traits::WFC, queueprop, queueprop_bitarrayset, queueprop_bitarrayset_fibheap, stackprop, ac4};
 
 fn criterion_benchmark_all(c: &mut Criterion) {
     let mut group = c.benchmark_group("wfc");
@@ -39,6 +39,12 @@ fn criterion_benchmark_all(c: &mut Criterion) {
             |b, i| b.iter(|| black_box(
                 queueprop_bitarrayset_fibheap::QueuePropBitArraySetFibHeap::solve(&i, &rules)
         )));
+        group.bench_with_input(
+            BenchmarkId::new("ac4", size),
+            &input,
+            |b, i| b.iter(|| black_box(
+                ac4::AC4::solve(&i, &rules)
+        )));
     }
     group.finish();
 }
@@ -99,5 +105,21 @@ fn criterion_benchmark_bitarray(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark_all, criterion_benchmark_queue_stack, criterion_benchmark_fibheap, criterion_benchmark_bitarray);
+fn criterion_benchmark_ac4(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ac4_only");
+    let rules = rules::get_pipes_rules();
+    let size = 34;
+    let input = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(size.pow(3) as u64));
+    group.bench_with_input(
+        BenchmarkId::new("ac4", size),
+        &input,
+        |b, i| b.iter(|| black_box(
+            ac4::AC4::solve(&i, &rules)
+    )));
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark_all, criterion_benchmark_queue_stack, criterion_benchmark_fibheap, criterion_benchmark_bitarray, criterion_benchmark_ac4);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,166 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, utils, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, PosIter3D, neighbors, CONSTRAINING_DIRECTION}, traits::{WFC, Contradiction}, budget::{Metering, SolveError}};
+
+//propagation keeping for every cell, direction and tile the number of tiles of the neighbor supporting it
+//tile is removed once its counter drops to zero, so propagation costs are proportional to removals
+pub struct AC4{}
+
+struct Supports{
+    //tiles which appear in rules, counters are indexed by position in this list
+    tiles: Vec<u8>,
+    index: Vec<usize>,
+    //supported[tile][direction] lists indexes of tiles which tile in the neighbor in direction allows in the cell
+    supported: Vec<[Vec<usize>; 6]>,
+    //all 256 tiles of a neighbor may support a tile, which does not fit in u8
+    counts: Vec<u16>
+}
+
+impl Supports
+{
+    fn new(rules: &[DirectionMapping<Bits256Set>], cells: usize) -> Supports
+    {
+        let tiles = QueuePropBitArraySet::any_tile(rules).items();
+        let mut index = vec![usize::MAX; u8::MAX as usize + 1];
+        for (i, &tile) in tiles.iter().enumerate()
+        {
+            index[tile as usize] = i;
+        }
+        let supported = (0..=u8::MAX as usize).map(|tile| {
+            std::array::from_fn(|direction| {
+                rules.get(tile).map_or(Vec::new(), |dir_rules| dir_rules.get(CONSTRAINING_DIRECTION[direction]).items().iter().map(|&other| index[other as usize]).collect())
+            })
+        }).collect();
+        let counts = vec![0; cells * 6 * tiles.len()];
+        Supports{tiles, index, supported, counts}
+    }
+
+    fn counter(&mut self, cell: usize, direction: usize, tile: usize) -> &mut u16
+    {
+        &mut self.counts[(cell * 6 + direction) * self.tiles.len() + tile]
+    }
+}
+
+impl AC4
+{
+    fn cell((x, y, z): (usize, usize, usize), solution: &Vec3D<Bits256Set>) -> usize
+    {
+        x + y * solution.x_size + z * solution.x_size * solution.y_size
+    }
+
+    //counts supports of every interior cell, tiles without support are queued for removal
+    fn initialize(solution: & mut Vec3D<Bits256Set>, supports: & mut Supports, removed: & mut VecDeque<((usize, usize, usize), u8)>) -> Result<(), Contradiction>
+    {
+        for (x, y, z) in PosIter3D::new_no_border(solution)
+        {
+            let cell = AC4::cell((x, y, z), solution);
+            for (direction, (nx, ny, nz)) in neighbors((x, y, z)).into_iter().enumerate()
+            {
+                for tile in solution.get(nx, ny, nz).items()
+                {
+                    for i in 0..supports.supported[tile as usize][direction].len()
+                    {
+                        let supported = supports.supported[tile as usize][direction][i];
+                        *supports.counter(cell, direction, supported) += 1;
+                    }
+                }
+            }
+        }
+        for (x, y, z) in PosIter3D::new_no_border(solution)
+        {
+            let cell = AC4::cell((x, y, z), solution);
+            let mut current = solution.get(x, y, z);
+            let mut wiping = None;
+            for direction in 0..6
+            {
+                for tile in current.items()
+                {
+                    if *supports.counter(cell, direction, supports.index[tile as usize]) == 0
+                    {
+                        current.remove(tile);
+                        removed.push_back(((x, y, z), tile));
+                    }
+                }
+                if current.is_empty() && wiping.is_none()
+                {
+                    wiping = Some(neighbors((x, y, z))[direction]);
+                }
+            }
+            solution.set(x, y, z, current);
+            if let Some(neighbor) = wiping
+            {
+                return Err(Contradiction::new((x, y, z), neighbor, 0, QueuePropBitArraySet::format_solution(solution)));
+            }
+        }
+        Ok(())
+    }
+
+    fn propagate<M: Metering>(solution: & mut Vec3D<Bits256Set>, supports: & mut Supports, removed: & mut VecDeque<((usize, usize, usize), u8)>, step: usize, meter: & mut M) -> Result<(), SolveError>
+    {
+        while let Some((position, tile)) = removed.pop_front() {
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(solution)});
+            }
+            for (direction, neighbor) in neighbors(position).into_iter().enumerate()
+            {
+                let (nx, ny, nz) = neighbor;
+                if solution.is_border(nx, ny, nz)
+                {
+                    continue;
+                }
+                //from the neighbor the removed tile lies in the opposite direction
+                let back = direction ^ 1;
+                let cell = AC4::cell(neighbor, solution);
+                let mut current = solution.get(nx, ny, nz);
+                for i in 0..supports.supported[tile as usize][back].len()
+                {
+                    let supported = supports.supported[tile as usize][back][i];
+                    let counter = supports.counter(cell, back, supported);
+                    *counter -= 1;
+                    if *counter == 0 && current.contains(supports.tiles[supported])
+                    {
+                        current.remove(supports.tiles[supported]);
+                        removed.push_back((neighbor, supports.tiles[supported]));
+                    }
+                }
+                solution.set(nx, ny, nz, current);
+                if current.is_empty()
+                {
+                    removed.clear();
+                    return Err(Contradiction::new(neighbor, position, step, QueuePropBitArraySet::format_solution(solution)).into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WFC for AC4{
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        QueuePropBitArraySet::check_map(map, &rules_internal)?;
+        let (mut solution, _) = QueuePropBitArraySet::prepare_wave(map, &rules_internal);
+        let mut supports = Supports::new(&rules_internal, map.x_size * map.y_size * map.z_size);
+        let mut removed = VecDeque::new();
+        AC4::initialize(&mut solution, &mut supports, &mut removed)?;
+        AC4::propagate(&mut solution, &mut supports, &mut removed, 0, meter)?;
+        let mut step = 0;
+        while let Some(((x, y, z), current)) = QueuePropBitArraySet::find_minimal(&solution) {
+            if let Err(reason) = meter.collapse()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(&solution)});
+            }
+            //minimal found setting it randomly, every other candidate is removed
+            let tile = utils::get_random_with(rng, current.items());
+            step += 1;
+            solution.set(x, y, z, Bits256Set::new_from_vec(vec![tile]));
+            removed.extend(current.items().into_iter().filter(|&other| other != tile).map(|other| ((x, y, z), other)));
+            AC4::propagate(&mut solution, &mut supports, &mut removed, step, meter)?;
+        }
+        Ok(QueuePropBitArraySet::format_solution(&solution))
+    }
+}
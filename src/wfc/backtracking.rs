@@ -0,0 +1,147 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, utils, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, neighbors}, traits::{WFC, Contradiction}, budget::{Metering, Unmetered, SolveError}};
+
+pub const DEFAULT_MAX_DECISIONS: usize = usize::MAX;
+pub const DEFAULT_MAX_BACKTRACKS: usize = 10_000;
+
+struct Decision
+{
+    position: (usize, usize, usize),
+    //candidates of the cell without the chosen tile
+    alternatives: Bits256Set,
+    //length of the trail before the decision was made
+    trail_len: usize
+}
+
+type Trail = Vec<((usize, usize, usize), Bits256Set)>;
+
+pub struct BackTracking{}
+
+impl BackTracking
+{
+    //max_decisions bounds the decision stack, older decisions are forgotten and can no longer be undone
+    //max_backtracks bounds the number of undone decisions before giving up
+    pub fn try_solve_with_limits(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], max_decisions: usize, max_backtracks: usize, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        BackTracking::solve(map, rules, max_decisions, max_backtracks, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)
+    }
+
+    fn solve<M: Metering>(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], max_decisions: usize, max_backtracks: usize, meter: & mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        let mut solution = QueuePropBitArraySet::prepare_map_metered(map, &rules_internal, meter)?;
+        let mut decisions = VecDeque::<Decision>::new();
+        let mut trail = Trail::new();
+        let mut step = 0;
+        let mut backtracks = 0;
+        loop {
+            //find minimal non zero entropy
+            let ((x, y, z), current) = match QueuePropBitArraySet::find_minimal(&solution) {
+                Some(minimal) => minimal,
+                //nothing left to be collapsed, returning solution
+                None => return Ok(QueuePropBitArraySet::format_solution(&solution))
+            };
+            if let Err(reason) = meter.collapse()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(&solution)});
+            }
+            let tile = utils::get_random_with(rng, current.items());
+            let mut alternatives = current;
+            alternatives.remove(tile);
+            decisions.push_back(Decision{position: (x, y, z), alternatives, trail_len: trail.len()});
+            step += 1;
+            let mut result = BackTracking::assign(&mut solution, &rules_internal, &mut trail, (x, y, z), Bits256Set::new_from_vec(vec![tile]), step, meter);
+            while let Err(SolveError::Contradiction(contradiction)) = result {
+                //undo to the last choice point and exclude the tile which failed there
+                let decision = match decisions.pop_back() {
+                    Some(decision) if backtracks < max_backtracks => decision,
+                    _ => return Err(contradiction.into())
+                };
+                backtracks += 1;
+                BackTracking::undo(&mut solution, &mut trail, decision.trail_len);
+                result = if decision.alternatives.is_empty() {
+                    Err(contradiction.into())
+                }
+                else {
+                    BackTracking::assign(&mut solution, &rules_internal, &mut trail, decision.position, decision.alternatives, step, meter)
+                };
+            }
+            //running out of budget is not undone
+            result?;
+            if decisions.len() > max_decisions
+            {
+                BackTracking::forget_oldest(&mut decisions, &mut trail);
+            }
+        }
+    }
+
+    fn assign<M: Metering>(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], trail: & mut Trail, position: (usize, usize, usize), value: Bits256Set, step: usize, meter: & mut M) -> Result<(), SolveError>
+    {
+        BackTracking::set(solution, trail, position, value);
+        let mut to_propagate = VecDeque::from(neighbors(position));
+        BackTracking::propagate(solution, rules, trail, &mut to_propagate, step, meter)
+    }
+
+    fn set(solution: & mut Vec3D<Bits256Set>, trail: & mut Trail, (x, y, z): (usize, usize, usize), value: Bits256Set)
+    {
+        trail.push(((x, y, z), solution.get(x, y, z)));
+        solution.set(x, y, z, value);
+    }
+
+    fn undo(solution: & mut Vec3D<Bits256Set>, trail: & mut Trail, trail_len: usize)
+    {
+        while trail.len() > trail_len {
+            let ((x, y, z), previous) = trail.pop().unwrap();
+            solution.set(x, y, z, previous);
+        }
+    }
+
+    fn forget_oldest(decisions: & mut VecDeque<Decision>, trail: & mut Trail)
+    {
+        decisions.pop_front();
+        //changes made before the oldest remaining decision can never be undone
+        let committed = decisions.front().map_or(trail.len(), |decision| decision.trail_len);
+        trail.drain(..committed);
+        for decision in decisions.iter_mut()
+        {
+            decision.trail_len -= committed;
+        }
+    }
+
+    fn propagate<M: Metering>(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], trail: & mut Trail, to_propagate: & mut VecDeque<(usize,usize,usize)>, step: usize, meter: & mut M) -> Result<(), SolveError>
+    {
+        while let Some((x, y, z)) = to_propagate.pop_front() {
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(solution)});
+            }
+            let current = solution.get(x, y, z);
+            if current.len() > 1
+            {
+                //excluded tiles must stay excluded, so legal tiles are narrowed down instead of recomputed
+                let updated = Bits256Set::new_intersection(vec![current, QueuePropBitArraySet::legal_tiles((x, y, z), solution, rules)]);
+                if updated != current {
+                    BackTracking::set(solution, trail, (x, y, z), updated);
+                    if updated.is_empty()
+                    {
+                        let neighbor = utils::wiping_neighbor(QueuePropBitArraySet::neighbor_constraints((x, y, z), solution, rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, step, QueuePropBitArraySet::format_solution(solution)).into());
+                    }
+                    //propagation changed state, need to continue propagation
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WFC for BackTracking{
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        BackTracking::solve(map, rules, DEFAULT_MAX_DECISIONS, DEFAULT_MAX_BACKTRACKS, meter, rng)
+    }
+}
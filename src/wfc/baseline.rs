@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 
-use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, vec3d::{Vec3D, PosIter3D}, traits::WFC};
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, vec3d::{Vec3D, PosIter3D, neighbors}, traits::{WFC, Contradiction}, budget::{Metering, SolveError}};
 
 pub struct BaseLine{}
 
@@ -8,11 +10,11 @@ impl BaseLine
 {
     fn vec_bool_set_from_set(set: &HashSet<u8>) -> Vec<bool>
     {
-        (0..u8::MAX).into_iter().map(|x| set.contains(&x)).collect()
+        (0..=u8::MAX).map(|x| set.contains(&x)).collect()
     }
     fn vec_bool_set_from(collection: &[u8]) -> Vec<bool>
     {
-        (0..u8::MAX).into_iter().map(|x| collection.contains(&x)).collect()
+        (0..=u8::MAX).map(|x| collection.contains(&x)).collect()
     }
     fn prepare_rules(rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec<DirectionMapping<Vec<bool>>>
     {
@@ -26,7 +28,7 @@ impl BaseLine
         )).collect()
     }
 
-    fn prepare_map(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec3D<Vec<bool>>
+    fn prepare_map<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Vec<bool>>>, meter: & mut M) -> Result<Vec3D<Vec<bool>>, SolveError>
     {
         let mut solution = Vec3D::<Vec<bool>>::new(map.x_size, map.y_size, map.z_size, Self::vec_bool_set_from_set(&rules::get_any_tile()));
         for (x, y, z) in PosIter3D::new(&solution){
@@ -35,11 +37,11 @@ impl BaseLine
                 solution.set(x, y, z, Self::vec_bool_set_from(&[val]));
             }
         }
-        BaseLine::propagate(&mut solution, rules);
-        solution
+        BaseLine::propagate(&mut solution, rules, 0, meter)?;
+        Ok(solution)
     }
 
-    fn propagate(solution: & mut Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> ()
+    fn propagate<M: Metering>(solution: & mut Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>, step: usize, meter: & mut M) -> Result<(), SolveError>
     {
         let mut changed = true;
         while changed{
@@ -47,8 +49,18 @@ impl BaseLine
             for (x, y, z) in PosIter3D::new_no_border(&solution) {
                 if solution.get(x, y, z).iter().filter(|&&x| x).count() > 1
                 {
+                    if let Err(reason) = meter.propagation()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: BaseLine::format_partial(solution)});
+                    }
                     let updated = BaseLine::legal_tiles(x, y, z, &solution, rules);
                     if updated != solution.get(x, y, z) {
+                        if !updated.contains(&true)
+                        {
+                            solution.set(x, y, z, updated);
+                            let neighbor = utils::wiping_neighbor(BaseLine::neighbor_constraints(x, y, z, solution, rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                            return Err(Contradiction::new((x, y, z), neighbor, step, BaseLine::format_solution(solution)).into());
+                        }
                         solution.set(x, y, z, updated);
                         changed = true;
                         //propagation changed state, need to continue propagation
@@ -56,6 +68,7 @@ impl BaseLine
                 }
             }
         }
+        Ok(())
     }
 
     fn find_minimal(solution: &Vec3D<Vec<bool>>) -> Option<(usize,usize,usize, Vec<bool>)>
@@ -97,66 +110,71 @@ impl BaseLine
 
     fn legal_tiles(x: usize, y: usize, z: usize, solution: &Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec<bool>
     {
-        let w: Vec<Vec<bool>> = vec![
+        BaseLine::neighbor_constraints(x, y, z, solution, rules)
+            .iter()
+            .fold(vec![true; u8::MAX as usize + 1], |acc, x| acc.iter()
+                                                            .zip(x)
+                                                            .map(|(&a,&b)| a && b)
+                                                            .collect())
+    }
+
+    fn neighbor_constraints(x: usize, y: usize, z: usize, solution: &Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec<Vec<bool>>
+    {
+        vec![
             solution.get(x, y + 1, z)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].down())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
                                                                              .collect()),
             solution.get(x, y - 1, z)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].up())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
                                                                              .collect()),
             solution.get(x - 1, y, z)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].left())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
                                                                              .collect()),
             solution.get(x + 1, y, z)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].right())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
                                                                              .collect()),
             solution.get(x, y, z - 1)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].front())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
                                                                              .collect()),
             solution.get(x, y, z + 1)
                     .iter()
-                    .zip(0..u8::MAX)
+                    .zip(0..=u8::MAX)
                     .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                     .map(|idx| rules[idx as usize].back())
-                    .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+                    .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                              .zip(b)
                                                                              .map(|(&a,&b)| a||b)
-                                                                             .collect())];
-
-        w.iter().fold(vec![true; u8::MAX as usize], |acc, x| acc.iter()
-                                                                                               .zip(x)
-                                                                                               .map(|(&a,&b)| a && b)
-                                                                                               .collect())
+                                                                             .collect())]
     }
 
     fn format_solution(solution: &Vec3D<Vec<bool>>) -> Vec3D<u8>
@@ -164,7 +182,7 @@ impl BaseLine
         let mut ret = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, EMPTY);
         for (x, y, z) in PosIter3D::new(&solution)
         {
-            ret.set(x, y, z, solution.get(x, y, z).iter().zip(0..u8::MAX).filter_map(|(&egz, x)|
+            ret.set(x, y, z, solution.get(x, y, z).iter().zip(0..=u8::MAX).filter_map(|(&egz, x)|
                 {
                     if egz
                     {
@@ -178,25 +196,42 @@ impl BaseLine
         ret
     }
 
-
+    //decided cells hold their tile and the rest is EMPTY
+    fn format_partial(solution: &Vec3D<Vec<bool>>) -> Vec3D<u8>
+    {
+        let mut ret = BaseLine::format_solution(solution);
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            if solution.get_ref(x, y, z).iter().filter(|&&x| x).count() > 1
+            {
+                ret.set(x, y, z, EMPTY);
+            }
+        }
+        ret
+    }
 }
 
 impl WFC for BaseLine{
-    fn solve(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
     {
         //prepare format
         let rules = &Self::prepare_rules(rules);
-        let mut solution = BaseLine::prepare_map(map, rules);
+        let mut solution = BaseLine::prepare_map(map, rules, meter)?;
+        let mut step = 0;
         loop {
             //propagation
-            BaseLine::propagate(&mut solution, rules);
+            BaseLine::propagate(&mut solution, rules, step, meter)?;
             //find minimal non zero entropy
             let minimal = BaseLine::find_minimal(&mut solution);
             match minimal {
                 Some((x,y, z, current)) => {
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: BaseLine::format_partial(&solution)});
+                    }
                     //minimal found setting it randomly
-                    solution.set(x, y, z, Self::vec_bool_set_from(&[utils::get_random(current.iter()
-                                                                                                                  .zip(0..u8::MAX)
+                    solution.set(x, y, z, Self::vec_bool_set_from(&[utils::get_random_with(rng, current.iter()
+                                                                                                                  .zip(0..=u8::MAX)
                                                                                                                   .filter_map(|(&egz,x)|{
                                                                                                                     if egz{
                                                                                                                         Some(x)
@@ -205,10 +240,11 @@ impl WFC for BaseLine{
                                                                                                                         None
                                                                                                                     }
                                                                                                                   }))]));
+                    step += 1;
                 },
                 None => {
                     //nothing left to be collapsed, returning solution
-                    return BaseLine::format_solution(&solution);
+                    return Ok(BaseLine::format_solution(&solution));
                 }
             }
         }
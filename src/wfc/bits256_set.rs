@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 
-#[derive(Copy, Clone, PartialEq)]
+use super::utils::Domain;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Bits256Set{
     first: u128,
     second: u128
@@ -12,6 +14,12 @@ impl Bits256Set{
         Bits256Set{first, second}
     }
 
+    //raw bits, inverse of new
+    pub fn parts(&self) -> (u128, u128)
+    {
+        (self.first, self.second)
+    }
+
     pub fn new_from_vec(items: Vec<u8>) -> Bits256Set
     {
         let mut bits = Bits256Set::new_empty();
@@ -79,10 +87,10 @@ impl Bits256Set{
     {
         if x / 128 == 0
         {
-            self.first = 2u128.pow(x as u32) & !self.first;
+            self.first &= !2u128.pow(x as u32);
         }
         else {
-            self.second = 2u128.pow(x as u32 % 128) & !self.second;
+            self.second &= !2u128.pow(x as u32 % 128);
         }
     }
 
@@ -91,9 +99,49 @@ impl Bits256Set{
         (self.first.count_ones() + self.second.count_ones()) as usize
     }
 
+    pub fn is_empty(&self) -> bool
+    {
+        self.first == 0 && self.second == 0
+    }
+
     pub fn items(&self) -> Vec<u8>
     {
-        (0..u8::MAX).filter(|&item| self.contains(item)).collect()
+        (0..=u8::MAX).filter(|&item| self.contains(item)).collect()
     }
 
-}
\ No newline at end of file
+}
+
+impl Domain for Bits256Set
+{
+    fn intersect_with(& mut self, other: &Self)
+    {
+        *self = Bits256Set::new_intersection(vec![*self, *other]);
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Bits256Set;
+
+    #[test]
+    fn test_remove(){
+        for removed in [3, 200]
+        {
+            let mut set = Bits256Set::new_from_vec(vec![1, 3, 130, 200]);
+            set.remove(removed);
+            assert!(!set.contains(removed));
+            assert_eq!(set.len(), 3);
+            assert!([1, 3, 130, 200].iter().filter(|&&tile| tile != removed).all(|&tile| set.contains(tile)));
+            //removing a missing tile leaves the set unchanged
+            let before = set;
+            set.remove(removed);
+            assert!(set == before);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//what lies behind an edge of the grid along one axis
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Boundary{
+    //BORDER tile outside of the grid, without storing it in the map
+    Fixed,
+    //grid wraps around, output tiles seamlessly along the axis
+    Periodic,
+    //nothing outside of the grid, cells at the edge are not constrained there
+    Open
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Neighbor{
+    Cell((usize, usize, usize)),
+    Border,
+    Outside
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Boundaries{
+    pub x: Boundary,
+    pub y: Boundary,
+    pub z: Boundary
+}
+
+impl Boundaries
+{
+    pub fn new(x: Boundary, y: Boundary, z: Boundary) -> Boundaries
+    {
+        Boundaries{x, y, z}
+    }
+
+    pub fn all(boundary: Boundary) -> Boundaries
+    {
+        Boundaries::new(boundary, boundary, boundary)
+    }
+
+    //neighbors in the same order as vec3d::neighbors
+    pub fn neighbors(&self, (x_size, y_size, z_size): (usize, usize, usize), (x, y, z): (usize, usize, usize)) -> [Neighbor; 6]
+    {
+        let along_y = |dy| Boundaries::step(self.y, y, dy, y_size).map_or_else(|neighbor| neighbor, |ny| Neighbor::Cell((x, ny, z)));
+        let along_x = |dx| Boundaries::step(self.x, x, dx, x_size).map_or_else(|neighbor| neighbor, |nx| Neighbor::Cell((nx, y, z)));
+        let along_z = |dz| Boundaries::step(self.z, z, dz, z_size).map_or_else(|neighbor| neighbor, |nz| Neighbor::Cell((x, y, nz)));
+        [along_y(1), along_y(-1), along_x(-1), along_x(1), along_z(-1), along_z(1)]
+    }
+
+    //coordinate moved by one along an axis, or what is there if it leaves the grid
+    fn step(boundary: Boundary, coordinate: usize, delta: isize, size: usize) -> Result<usize, Neighbor>
+    {
+        let moved = coordinate as isize + delta;
+        if moved >= 0 && moved < size as isize
+        {
+            return Ok(moved as usize);
+        }
+        match boundary {
+            Boundary::Fixed => Err(Neighbor::Border),
+            Boundary::Periodic => Ok(moved.rem_euclid(size as isize) as usize),
+            Boundary::Open => Err(Neighbor::Outside)
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules::{EMPTY, BORDER}, utils, bits256_set::Bits256Set, boundary::{Boundaries, Boundary, Neighbor}, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, PosIter3D, CONSTRAINING_DIRECTION}, traits::{WFC, Contradiction}, budget::{Metering, Unmetered, SolveError}};
+
+//queue propagation which looks up neighbors through a boundary policy per axis
+//every cell of the map is output, there is no need for a BORDER shell
+pub struct Bounded{}
+
+impl Bounded
+{
+    pub fn try_solve_bounded(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], boundaries: Boundaries, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        Bounded::try_solve_bounded_metered(map, rules, boundaries, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)
+    }
+
+    pub fn try_solve_bounded_metered<M: Metering>(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], boundaries: Boundaries, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        //BORDER only lies behind fixed edges, it is never chosen for a cell
+        let mut any = QueuePropBitArraySet::any_tile(&rules_internal);
+        any.remove(BORDER);
+        let mut solution = Vec3D::new(map.x_size, map.y_size, map.z_size, any);
+        for (x, y, z) in PosIter3D::new(map)
+        {
+            let val = map.get(x, y, z);
+            if val != EMPTY
+            {
+                solution.set(x, y, z, Bits256Set::new_from_vec(vec![val]));
+            }
+        }
+        //every cell may be constrained by fixed tiles or by the border behind the edge
+        let mut to_propagate = VecDeque::from_iter(PosIter3D::new(&solution));
+        Bounded::propagate(&mut solution, &rules_internal, boundaries, &mut to_propagate, 0, meter)?;
+        let mut step = 0;
+        while let Some(((x, y, z), current)) = QueuePropBitArraySet::find_minimal(&solution) {
+            if let Err(reason) = meter.collapse()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(&solution)});
+            }
+            solution.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_random_with(rng, current.items())]));
+            step += 1;
+            let mut to_propagate = VecDeque::from_iter(Bounded::cells(&solution, boundaries, (x, y, z)));
+            Bounded::propagate(&mut solution, &rules_internal, boundaries, &mut to_propagate, step, meter)?;
+        }
+        Ok(QueuePropBitArraySet::format_solution(&solution))
+    }
+
+    fn propagate<M: Metering>(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], boundaries: Boundaries, to_propagate: & mut VecDeque<(usize, usize, usize)>, step: usize, meter: & mut M) -> Result<(), SolveError>
+    {
+        while let Some((x, y, z)) = to_propagate.pop_front() {
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(solution)});
+            }
+            let current = solution.get(x, y, z);
+            if current.len() > 1
+            {
+                let constraints = Bounded::neighbor_constraints(solution, rules, boundaries, (x, y, z));
+                //cells never get back tiles they lost, such as BORDER which is removed at start
+                let updated = Bits256Set::new_intersection(constraints.iter().map(|&(constraint, _)| constraint).chain([current]).collect());
+                if updated != current {
+                    solution.set(x, y, z, updated);
+                    if updated.is_empty()
+                    {
+                        let neighbor = utils::wiping_neighbor(constraints, (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, step, QueuePropBitArraySet::format_solution(solution)).into());
+                    }
+                    //propagation changed state, need to continue propagation
+                    to_propagate.extend(Bounded::cells(solution, boundaries, (x, y, z)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //neighbors which are cells of the grid
+    fn cells(solution: &Vec3D<Bits256Set>, boundaries: Boundaries, position: (usize, usize, usize)) -> Vec<(usize, usize, usize)>
+    {
+        boundaries.neighbors((solution.x_size, solution.y_size, solution.z_size), position).iter()
+            .filter_map(|neighbor| match neighbor {
+                Neighbor::Cell(cell) => Some(*cell),
+                _ => None
+            })
+            .collect()
+    }
+
+    //constraint of every neighbor together with its position, the border reports the cell itself
+    fn neighbor_constraints(solution: &Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], boundaries: Boundaries, position: (usize, usize, usize)) -> Vec<(Bits256Set, (usize, usize, usize))>
+    {
+        boundaries.neighbors((solution.x_size, solution.y_size, solution.z_size), position).iter().zip(CONSTRAINING_DIRECTION).map(|(neighbor, direction)| {
+            match *neighbor {
+                Neighbor::Cell((x, y, z)) => (Bits256Set::new_sum(solution.get(x, y, z).items().iter().map(|&tile| *rules[tile as usize].get(direction)).collect()), (x, y, z)),
+                Neighbor::Border => (*rules[BORDER as usize].get(direction), position),
+                Neighbor::Outside => (Bits256Set::new_any(), position)
+            }
+        }).collect()
+    }
+
+}
+
+impl WFC for Bounded{
+    //map cells on the edge keep their role, everything behind them is BORDER
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        Bounded::try_solve_bounded_metered(map, rules, Boundaries::all(Boundary::Fixed), meter, rng)
+    }
+}
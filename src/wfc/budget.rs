@@ -0,0 +1,206 @@
+use std::{fmt, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
+
+use super::{traits::{Contradiction, Grid, Grid3D}, tile::Tile};
+
+//time is read only every this many propagations
+const TIME_CHECK_INTERVAL: usize = 256;
+
+//shared flag for stopping a solve running on another thread
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken{
+    cancelled: Arc<AtomicBool>
+}
+
+impl CancelToken
+{
+    pub fn new() -> CancelToken
+    {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self)
+    {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool
+    {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+//limits of a single solve, everything is unlimited by default
+#[derive(Clone, Default, Debug)]
+pub struct Budget{
+    pub max_time: Option<Duration>,
+    pub max_collapses: Option<usize>,
+    //number of cells taken from propagation queue
+    pub max_propagations: Option<usize>,
+    pub cancel: Option<CancelToken>
+}
+
+impl Budget
+{
+    pub fn new() -> Budget
+    {
+        Budget::default()
+    }
+
+    pub fn max_time(mut self, max_time: Duration) -> Budget
+    {
+        self.max_time = Some(max_time);
+        self
+    }
+
+    pub fn max_collapses(mut self, max_collapses: usize) -> Budget
+    {
+        self.max_collapses = Some(max_collapses);
+        self
+    }
+
+    pub fn max_propagations(mut self, max_propagations: usize) -> Budget
+    {
+        self.max_propagations = Some(max_propagations);
+        self
+    }
+
+    pub fn cancel(mut self, cancel: CancelToken) -> Budget
+    {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason{
+    Cancelled,
+    TimeExceeded,
+    CollapsesExceeded,
+    PropagationsExceeded
+}
+
+#[derive(Clone, Debug)]
+pub enum SolveError<G: Grid = Grid3D, T: Tile = u8>{
+    Contradiction(Contradiction<T, G>),
+    //budget ran out, undecided cells of partial are EMPTY so it can be solved again as a map
+    Stopped{reason: StopReason, step: usize, partial: G::Cells<T>},
+    //global constraint can not be met any more while every cell still has candidates, so no position is to blame
+    Unsatisfiable{step: usize, partial: G::Cells<T>}
+}
+
+impl<G: Grid, T: Tile> SolveError<G, T>
+{
+    //error of a solve whose meter never stops and which has no global constraints
+    pub(crate) fn into_contradiction(self) -> Contradiction<T, G>
+    {
+        match self {
+            SolveError::Contradiction(contradiction) => contradiction,
+            SolveError::Stopped{..} | SolveError::Unsatisfiable{..} => unreachable!("unlimited propagation only finds contradictions")
+        }
+    }
+}
+
+impl<G: Grid, T: Tile> fmt::Display for SolveError<G, T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Contradiction(contradiction) => contradiction.fmt(f),
+            SolveError::Stopped{reason, step, ..} => write!(f, "stopped after {} steps: {:?}", step, reason),
+            SolveError::Unsatisfiable{step, ..} => write!(f, "constraints can not be met after {} steps", step)
+        }
+    }
+}
+
+impl<G: Grid + fmt::Debug, T: Tile> std::error::Error for SolveError<G, T> {}
+
+impl<G: Grid, T: Tile> From<Contradiction<T, G>> for SolveError<G, T>
+{
+    fn from(contradiction: Contradiction<T, G>) -> Self {
+        SolveError::Contradiction(contradiction)
+    }
+}
+
+//checks made by metered solves before every collapse and for every cell taken from propagation queue
+//Send so that restarts running on several threads can share one meter
+pub trait Metering: Send{
+    fn collapse(&mut self) -> Result<(), StopReason>;
+    fn propagation(&mut self) -> Result<(), StopReason>;
+}
+
+//metering of solves without budget, compiles down to nothing
+pub struct Unmetered{}
+
+impl Metering for Unmetered
+{
+    #[inline(always)]
+    fn collapse(&mut self) -> Result<(), StopReason>
+    {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn propagation(&mut self) -> Result<(), StopReason>
+    {
+        Ok(())
+    }
+}
+
+//spending of a budget during one solve
+pub struct Meter{
+    budget: Budget,
+    started: Instant,
+    collapses: usize,
+    propagations: usize
+}
+
+impl Meter
+{
+    pub fn new(budget: &Budget) -> Meter
+    {
+        Meter{budget: budget.clone(), started: Instant::now(), collapses: 0, propagations: 0}
+    }
+
+    fn check_time(&self) -> Result<(), StopReason>
+    {
+        if self.budget.max_time.is_some_and(|max| self.started.elapsed() >= max)
+        {
+            return Err(StopReason::TimeExceeded);
+        }
+        self.check_cancel()
+    }
+
+    fn check_cancel(&self) -> Result<(), StopReason>
+    {
+        match &self.budget.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(StopReason::Cancelled),
+            _ => Ok(())
+        }
+    }
+}
+
+impl Metering for Meter
+{
+    fn collapse(&mut self) -> Result<(), StopReason>
+    {
+        if self.budget.max_collapses.is_some_and(|max| self.collapses >= max)
+        {
+            return Err(StopReason::CollapsesExceeded);
+        }
+        self.collapses += 1;
+        self.check_time()
+    }
+
+    fn propagation(&mut self) -> Result<(), StopReason>
+    {
+        if self.budget.max_propagations.is_some_and(|max| self.propagations >= max)
+        {
+            return Err(StopReason::PropagationsExceeded);
+        }
+        self.propagations += 1;
+        if self.propagations % TIME_CHECK_INTERVAL == 0
+        {
+            return self.check_time();
+        }
+        self.check_cancel()
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use super::{direction_mapping::DirectionMapping, rules::EMPTY, boundary::{Boundaries, Boundary}, bounded::Bounded, vec3d::{Vec3D, PosIter3D}, traits::Contradiction};
+
+pub const DEFAULT_MAX_ATTEMPTS: usize = 10;
+
+//solves an unbounded world chunk by chunk, in any order
+//cells of already generated chunks around a new chunk are fixed, so chunks agree at their seams
+pub struct ChunkGenerator{
+    rules: Vec<DirectionMapping<HashSet<u8>>>,
+    chunk_size: (usize, usize, usize),
+    seed: u64,
+    max_attempts: usize,
+    chunks: HashMap<(i64, i64, i64), Vec3D<u8>>
+}
+
+impl ChunkGenerator
+{
+    pub fn new(rules: &[DirectionMapping<HashSet<u8>>], chunk_size: (usize, usize, usize), seed: u64) -> ChunkGenerator
+    {
+        ChunkGenerator::with_max_attempts(rules, chunk_size, seed, DEFAULT_MAX_ATTEMPTS)
+    }
+
+    //chunk which runs into contradiction is solved again with next seed, up to max_attempts times
+    pub fn with_max_attempts(rules: &[DirectionMapping<HashSet<u8>>], chunk_size: (usize, usize, usize), seed: u64, max_attempts: usize) -> ChunkGenerator
+    {
+        ChunkGenerator{rules: rules.to_vec(), chunk_size, seed, max_attempts, chunks: HashMap::new()}
+    }
+
+    pub fn chunk_size(&self) -> (usize, usize, usize)
+    {
+        self.chunk_size
+    }
+
+    pub fn chunk(&self, coords: (i64, i64, i64)) -> Option<&Vec3D<u8>>
+    {
+        self.chunks.get(&coords)
+    }
+
+    //tile at world position, None if its chunk was not generated yet
+    pub fn tile(&self, (x, y, z): (i64, i64, i64)) -> Option<u8>
+    {
+        let (cx, cy, cz) = (self.chunk_size.0 as i64, self.chunk_size.1 as i64, self.chunk_size.2 as i64);
+        self.chunk((x.div_euclid(cx), y.div_euclid(cy), z.div_euclid(cz)))
+            .map(|chunk| chunk.get(x.rem_euclid(cx) as usize, y.rem_euclid(cy) as usize, z.rem_euclid(cz) as usize))
+    }
+
+    //seed of a chunk depends only on the world seed, chunk coordinates and attempt
+    pub fn chunk_seed(&self, (x, y, z): (i64, i64, i64), attempt: usize) -> u64
+    {
+        [x as u64, y as u64, z as u64, attempt as u64].iter().fold(ChunkGenerator::mix(self.seed), |hash, &value| ChunkGenerator::mix(hash ^ value))
+    }
+
+    //returns already generated chunk without solving it again
+    pub fn generate(&mut self, coords: (i64, i64, i64)) -> Result<&Vec3D<u8>, Contradiction>
+    {
+        if !self.chunks.contains_key(&coords)
+        {
+            let chunk = self.solve_chunk(coords)?;
+            self.chunks.insert(coords, chunk);
+        }
+        Ok(&self.chunks[&coords])
+    }
+
+    fn solve_chunk(&self, coords: (i64, i64, i64)) -> Result<Vec3D<u8>, Contradiction>
+    {
+        let map = self.seam_map(coords);
+        //cells outside the shell do not exist yet, they must not constrain the chunk
+        let boundaries = Boundaries::all(Boundary::Open);
+        let mut attempt = 0;
+        let solution = loop {
+            match Bounded::try_solve_bounded(&map, &self.rules, boundaries, &mut StdRng::seed_from_u64(self.chunk_seed(coords, attempt))) {
+                Ok(solution) => break solution,
+                Err(contradiction) if attempt + 1 >= self.max_attempts => return Err(contradiction),
+                Err(_) => attempt += 1
+            }
+        };
+        let (x_size, y_size, z_size) = self.chunk_size;
+        let mut chunk = Vec3D::new(x_size, y_size, z_size, EMPTY);
+        for (x, y, z) in PosIter3D::new(&chunk)
+        {
+            chunk.set(x, y, z, solution.get(x + 1, y + 1, z + 1));
+        }
+        Ok(chunk)
+    }
+
+    //chunk with one cell shell, cells of generated chunks are copied into the shell and the rest is EMPTY
+    //EMPTY shell cells are solved along with the chunk and dropped, so the chunk leaves room for its neighbors
+    fn seam_map(&self, (cx, cy, cz): (i64, i64, i64)) -> Vec3D<u8>
+    {
+        let (x_size, y_size, z_size) = self.chunk_size;
+        let mut map = Vec3D::new(x_size + 2, y_size + 2, z_size + 2, EMPTY);
+        for (x, y, z) in PosIter3D::new(&map)
+        {
+            if !map.is_border(x, y, z)
+            {
+                continue;
+            }
+            let world = (cx * x_size as i64 + x as i64 - 1, cy * y_size as i64 + y as i64 - 1, cz * z_size as i64 + z as i64 - 1);
+            if let Some(tile) = self.tile(world)
+            {
+                map.set(x, y, z, tile);
+            }
+        }
+        map
+    }
+
+    //splitmix64 finalizer
+    fn mix(value: u64) -> u64
+    {
+        let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{rules::{self, TYPE_MASK}, direction_mapping::{DirectionMapping, OFFSETS, opposite}, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, PosIter3D, neighbors}, traits::Contradiction, budget::SolveError};
+
+//all interior cells holding a member tile have to form a single connected component
+//two neighboring members are linked if both are open towards each other
+pub struct Connectivity{
+    pub members: HashSet<u8>,
+    pub open: Vec<DirectionMapping<bool>>
+}
+
+impl Connectivity
+{
+    pub fn new(members: HashSet<u8>, open: Vec<DirectionMapping<bool>>) -> Connectivity
+    {
+        Connectivity{members, open}
+    }
+
+    pub fn from_fn<F: Fn(u8, usize) -> bool>(members: HashSet<u8>, open: F) -> Connectivity
+    {
+        let open = (0..=u8::MAX).map(|tile| {
+            let mut direction = 0;
+            DirectionMapping::from_fn(|_| {
+                direction += 1;
+                open(tile, direction - 1)
+            })
+        }).collect();
+        Connectivity::new(members, open)
+    }
+
+    //members are linked to every neighboring member
+    pub fn adjacent(members: HashSet<u8>) -> Connectivity
+    {
+        Connectivity::from_fn(members, |_, _| true)
+    }
+}
+
+//pipes of pipe_type linked through their openings, pipe bits in the order of DirectionMapping::new arguments
+pub fn get_pipes_connectivity(pipe_type: u8) -> Connectivity
+{
+    let directions = [rules::UP, rules::DOWN, rules::RIGHT, rules::LEFT, rules::FRONT, rules::BACK];
+    let members = (0..=u8::MAX).filter(|&a| a & TYPE_MASK == pipe_type && a & !TYPE_MASK != 0).collect();
+    Connectivity::from_fn(members, |a, direction| a & directions[direction] != 0)
+}
+
+//members and members open in each direction
+struct Groups{
+    members: Bits256Set,
+    open: Vec<Bits256Set>
+}
+
+impl Groups
+{
+    fn new(connectivity: &Connectivity) -> Groups
+    {
+        let members = Bits256Set::new_from_hash_set(&connectivity.members);
+        let open = (0..OFFSETS.len()).map(|direction| {
+            let open: Vec<u8> = members.items().into_iter().filter(|&tile| *connectivity.open[tile as usize].get(direction)).collect();
+            Bits256Set::new_from_vec(open)
+        }).collect();
+        Groups{members, open}
+    }
+
+    fn can_be_member(&self, domain: Bits256Set) -> bool
+    {
+        !Bits256Set::new_intersection(vec![domain, self.members]).is_empty()
+    }
+
+    fn is_required(&self, domain: Bits256Set) -> bool
+    {
+        !domain.is_empty() && Bits256Set::new_intersection(vec![domain, self.members]) == domain
+    }
+}
+
+fn step(solution: &Vec3D<Bits256Set>, (x, y, z): (usize, usize, usize), direction: usize) -> Option<(usize, usize, usize)>
+{
+    let (dx, dy, dz) = OFFSETS[direction];
+    let position = ((x as isize + dx) as usize, (y as isize + dy) as usize, (z as isize + dz) as usize);
+    (!solution.is_border(position.0, position.1, position.2)).then_some(position)
+}
+
+//neighbors which can still be linked with position by tiles the rules allow next to each other, with the direction towards them
+fn links(solution: &Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], groups: &Groups, position: (usize, usize, usize)) -> Vec<((usize, usize, usize), usize)>
+{
+    let (x, y, z) = position;
+    let domain = solution.get(x, y, z);
+    (0..OFFSETS.len()).filter_map(|direction| {
+        let neighbor = step(solution, position, direction)?;
+        let other = Bits256Set::new_intersection(vec![solution.get(neighbor.0, neighbor.1, neighbor.2), groups.open[opposite(direction)]]);
+        let linked = !other.is_empty() && Bits256Set::new_intersection(vec![domain, groups.open[direction]]).items().into_iter()
+            .any(|tile| !Bits256Set::new_intersection(vec![*rules[tile as usize].get(direction), other]).is_empty());
+        linked.then_some((neighbor, direction))
+    }).collect()
+}
+
+//with endpoints only they have to be members linked together, other members may form further components
+//otherwise every member is required and members which can not reach the required ones are removed
+//forces members on cells and links every path between required cells goes through, split required cells can not be joined again
+//cause is the cell collapsed last, if any, it is reported as neighbor of cells left without tiles
+pub(crate) fn enforce(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], connectivity: &Connectivity, endpoints: Option<&[(usize, usize, usize)]>, cause: Option<(usize, usize, usize)>, step: usize) -> Result<(), SolveError>
+{
+    let groups = Groups::new(connectivity);
+    loop {
+        let required: Vec<(usize, usize, usize)> = match endpoints {
+            Some(endpoints) => endpoints.to_vec(),
+            None => PosIter3D::new_no_border(solution).filter(|&(x, y, z)| groups.is_required(solution.get(x, y, z))).collect()
+        };
+        let Some(&root) = required.first() else {
+            return Ok(());
+        };
+        let mut is_required = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, false);
+        for &(x, y, z) in &required
+        {
+            is_required.set(x, y, z, true);
+        }
+        let restrictions = restrictions(solution, rules, &groups, &is_required, root);
+        if required.iter().any(|&(x, y, z)| restrictions.order.get(x, y, z) == usize::MAX || !groups.can_be_member(solution.get(x, y, z)))
+        {
+            return Err(SolveError::Unsatisfiable{step, partial: QueuePropBitArraySet::format_partial(solution)});
+        }
+        let mut to_propagate = VecDeque::new();
+        for (x, y, z) in PosIter3D::new_no_border(solution)
+        {
+            let current = solution.get(x, y, z);
+            let updated = if endpoints.is_none() && restrictions.order.get(x, y, z) == usize::MAX && groups.can_be_member(current) {
+                let (first, second) = groups.members.parts();
+                Bits256Set::new_intersection(vec![current, Bits256Set::new(!first, !second)])
+            } else if is_required.get(x, y, z) {
+                Bits256Set::new_intersection(vec![current, groups.members, restrictions.allowed.get(x, y, z)])
+            } else {
+                Bits256Set::new_intersection(vec![current, restrictions.allowed.get(x, y, z)])
+            };
+            if updated != current
+            {
+                solution.set(x, y, z, updated);
+                if updated.is_empty()
+                {
+                    return Err(Contradiction::new((x, y, z), cause.unwrap_or((x, y, z)), step, QueuePropBitArraySet::format_solution(solution)).into());
+                }
+                to_propagate.extend(neighbors((x, y, z)));
+            }
+        }
+        if to_propagate.is_empty()
+        {
+            return Ok(());
+        }
+        QueuePropBitArraySet::propagate(solution, rules, &mut to_propagate, step)?;
+    }
+}
+
+//depth first search order of cells reachable from root, usize::MAX if unreachable
+//and tiles each cell is restricted to by articulation points and bridges separating required cells
+struct Restrictions{
+    order: Vec3D<usize>,
+    allowed: Vec3D<Bits256Set>
+}
+
+fn restrictions(solution: &Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], groups: &Groups, is_required: &Vec3D<bool>, root: (usize, usize, usize)) -> Restrictions
+{
+    let (x_size, y_size, z_size) = (solution.x_size, solution.y_size, solution.z_size);
+    let mut order = Vec3D::new(x_size, y_size, z_size, usize::MAX);
+    let mut low = Vec3D::new(x_size, y_size, z_size, usize::MAX);
+    let mut required = Vec3D::new(x_size, y_size, z_size, 0usize);
+    let mut allowed = Vec3D::new(x_size, y_size, z_size, Bits256Set::new_any());
+    let mut visited = 0;
+    //cell, direction it was entered from and links still to visit
+    let mut stack = vec![(root, None, links(solution, rules, groups, root))];
+    order.set(root.0, root.1, root.2, visited);
+    low.set(root.0, root.1, root.2, visited);
+    required.set(root.0, root.1, root.2, 1);
+    while let Some((cell, entered, pending)) = stack.last_mut()
+    {
+        let cell = *cell;
+        if let Some((next, direction)) = pending.pop()
+        {
+            if entered.is_some_and(|entered| opposite(entered) == direction)
+            {
+                continue;
+            }
+            let (nx, ny, nz) = next;
+            if order.get(nx, ny, nz) == usize::MAX
+            {
+                visited += 1;
+                order.set(nx, ny, nz, visited);
+                low.set(nx, ny, nz, visited);
+                required.set(nx, ny, nz, is_required.get(nx, ny, nz) as usize);
+                stack.push((next, Some(direction), links(solution, rules, groups, next)));
+            }
+            else
+            {
+                let lowest = usize::min(low.get(cell.0, cell.1, cell.2), order.get(nx, ny, nz));
+                low.set(cell.0, cell.1, cell.2, lowest);
+            }
+            continue;
+        }
+        let entered = *entered;
+        stack.pop();
+        let Some(direction) = entered else {
+            continue;
+        };
+        let (parent, _, _) = stack.last().unwrap();
+        let parent = *parent;
+        let (px, py, pz) = parent;
+        let (cx, cy, cz) = cell;
+        low.set(px, py, pz, usize::min(low.get(px, py, pz), low.get(cx, cy, cz)));
+        required.set(px, py, pz, required.get(px, py, pz) + required.get(cx, cy, cz));
+        //root is required, so required cells below cell are separated from it without parent
+        if required.get(cx, cy, cz) == 0
+        {
+            continue;
+        }
+        if low.get(cx, cy, cz) >= order.get(px, py, pz)
+        {
+            allowed.set(px, py, pz, Bits256Set::new_intersection(vec![allowed.get(px, py, pz), groups.members]));
+        }
+        if low.get(cx, cy, cz) > order.get(px, py, pz)
+        {
+            allowed.set(px, py, pz, Bits256Set::new_intersection(vec![allowed.get(px, py, pz), groups.open[direction]]));
+            allowed.set(cx, cy, cz, Bits256Set::new_intersection(vec![allowed.get(cx, cy, cz), groups.open[opposite(direction)]]));
+        }
+    }
+    Restrictions{order, allowed}
+}
@@ -0,0 +1,122 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::{Rng, RngCore};
+
+use super::{direction_mapping::DirectionMapping, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, PosIter3D, neighbors}, traits::Contradiction, budget::SolveError};
+
+//number of interior cells holding any tile of the group has to be within min..=max
+#[derive(Clone, Debug)]
+pub struct CountConstraint{
+    pub tiles: HashSet<u8>,
+    pub min: usize,
+    pub max: usize
+}
+
+impl CountConstraint
+{
+    pub fn new(tiles: HashSet<u8>, min: usize, max: usize) -> CountConstraint
+    {
+        CountConstraint{tiles, min, max}
+    }
+
+    pub fn exactly(tiles: HashSet<u8>, count: usize) -> CountConstraint
+    {
+        CountConstraint::new(tiles, count, count)
+    }
+
+    pub fn at_least(tiles: HashSet<u8>, min: usize) -> CountConstraint
+    {
+        CountConstraint::new(tiles, min, usize::MAX)
+    }
+
+    pub fn at_most(tiles: HashSet<u8>, max: usize) -> CountConstraint
+    {
+        CountConstraint::new(tiles, 0, max)
+    }
+}
+
+//once a group reached its maximum it is removed from undecided cells
+//once only as many cells as its minimum can hold a group they are restricted to it
+//cells left without tiles are blamed on cause, which is the last collapsed cell if there is one
+pub(crate) fn enforce(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], constraints: &[CountConstraint], cause: Option<(usize, usize, usize)>, step: usize) -> Result<(), SolveError>
+{
+    let groups: Vec<Bits256Set> = constraints.iter().map(|constraint| Bits256Set::new_from_hash_set(&constraint.tiles)).collect();
+    loop {
+        let mut to_propagate = VecDeque::new();
+        for (constraint, &group) in constraints.iter().zip(&groups)
+        {
+            let (decided, possible) = count(solution, group);
+            if decided > constraint.max || possible < constraint.min
+            {
+                return Err(SolveError::Unsatisfiable{step, partial: QueuePropBitArraySet::format_partial(solution)});
+            }
+            for (x, y, z) in PosIter3D::new_no_border(solution)
+            {
+                let current = solution.get(x, y, z);
+                let in_group = Bits256Set::new_intersection(vec![current, group]);
+                if current.len() <= 1 || in_group.is_empty()
+                {
+                    continue;
+                }
+                let updated = if decided == constraint.max {
+                    Bits256Set::new_intersection(vec![current, complement(group)])
+                } else if possible == constraint.min {
+                    in_group
+                } else {
+                    current
+                };
+                if updated != current
+                {
+                    solution.set(x, y, z, updated);
+                    if updated.is_empty()
+                    {
+                        return Err(Contradiction::new((x, y, z), cause.unwrap_or((x, y, z)), step, QueuePropBitArraySet::format_solution(solution)).into());
+                    }
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
+            }
+        }
+        if to_propagate.is_empty()
+        {
+            return Ok(());
+        }
+        QueuePropBitArraySet::propagate(solution, rules, &mut to_propagate, step)?;
+    }
+}
+
+//candidates for collapsing a cell, groups behind their minimum are chosen with probability
+//of missing cells over cells which still can take them, so minimums are paced through the solve
+pub(crate) fn select(solution: &Vec3D<Bits256Set>, candidates: Bits256Set, constraints: &[CountConstraint], rng: &mut dyn RngCore) -> Bits256Set
+{
+    for constraint in constraints
+    {
+        let group = Bits256Set::new_from_hash_set(&constraint.tiles);
+        let in_group = Bits256Set::new_intersection(vec![candidates, group]);
+        if in_group.is_empty() || in_group == candidates
+        {
+            continue;
+        }
+        let (decided, possible) = count(solution, group);
+        if decided < constraint.min && rng.gen_bool((constraint.min - decided) as f64 / (possible - decided) as f64)
+        {
+            return in_group;
+        }
+    }
+    candidates
+}
+
+//interior cells decided to a tile of group and cells which still can hold one
+fn count(solution: &Vec3D<Bits256Set>, group: Bits256Set) -> (usize, usize)
+{
+    PosIter3D::new_no_border(solution).fold((0, 0), |(decided, possible), (x, y, z)| {
+        let in_group = Bits256Set::new_intersection(vec![solution.get(x, y, z), group]).len();
+        let is_decided = solution.get(x, y, z).len() == 1 && in_group == 1;
+        (decided + is_decided as usize, possible + (in_group > 0) as usize)
+    })
+}
+
+fn complement(set: Bits256Set) -> Bits256Set
+{
+    let (first, second) = set.parts();
+    Bits256Set::new(!first, !second)
+}
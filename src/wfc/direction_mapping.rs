@@ -1,4 +1,16 @@
 
+//offset from a tile to the neighbor which each direction constrains, in the order of DirectionMapping::new arguments
+pub const OFFSETS: [(isize, isize, isize); 6] = [(0, 1, 0), (0, -1, 0), (-1, 0, 0), (1, 0, 0), (0, 0, 1), (0, 0, -1)];
+
+pub const NAMES: [&str; 6] = ["up", "down", "right", "left", "front", "back"];
+
+//direction pointing back from the neighbor, in the order of OFFSETS
+pub fn opposite(direction: usize) -> usize
+{
+    direction ^ 1
+}
+
+#[derive(Clone)]
 pub struct DirectionMapping<T>{
     repr: Vec::<T>
 }
@@ -9,6 +21,18 @@ impl <T> DirectionMapping<T>{
             repr: vec![up, down, right, left, front, back]
         }
     }
+    pub fn from_fn<F: FnMut((isize, isize, isize)) -> T>(f: F) -> DirectionMapping<T>{
+        DirectionMapping{
+            repr: OFFSETS.iter().cloned().map(f).collect()
+        }
+    }
+    //index in the order of OFFSETS
+    pub fn get(&self, direction: usize) -> &T{
+        &self.repr[direction]
+    }
+    pub fn get_mut(&mut self, direction: usize) -> &mut T{
+        &mut self.repr[direction]
+    }
     pub fn up(&self) -> &T{
         &self.repr[0]
     }
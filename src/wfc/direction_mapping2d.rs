@@ -0,0 +1,48 @@
+use super::direction_mapping::DirectionMapping;
+
+//offset from a tile to the neighbor which each direction constrains, in the order of DirectionMapping2D::new arguments
+pub const OFFSETS: [(isize, isize); 4] = [(0, 1), (0, -1), (-1, 0), (1, 0)];
+
+#[derive(Clone)]
+pub struct DirectionMapping2D<T>{
+    repr: Vec::<T>
+}
+
+impl <T> DirectionMapping2D<T>{
+    pub fn new(up: T, down: T, right: T, left: T) -> DirectionMapping2D<T>{
+        DirectionMapping2D{
+            repr: vec![up, down, right, left]
+        }
+    }
+    pub fn from_fn<F: FnMut((isize, isize)) -> T>(f: F) -> DirectionMapping2D<T>{
+        DirectionMapping2D{
+            repr: OFFSETS.iter().cloned().map(f).collect()
+        }
+    }
+    //index in the order of OFFSETS
+    pub fn get(&self, direction: usize) -> &T{
+        &self.repr[direction]
+    }
+    pub fn get_mut(&mut self, direction: usize) -> &mut T{
+        &mut self.repr[direction]
+    }
+    pub fn up(&self) -> &T{
+        &self.repr[0]
+    }
+    pub fn down(&self) -> &T{
+        &self.repr[1]
+    }
+    pub fn right(&self) -> &T{
+        &self.repr[2]
+    }
+    pub fn left(&self) -> &T{
+        &self.repr[3]
+    }
+}
+
+impl <T: Clone> DirectionMapping2D<T>{
+    //directions of the xy plane, front and back are dropped
+    pub fn from_3d(mapping: &DirectionMapping<T>) -> DirectionMapping2D<T>{
+        DirectionMapping2D::new(mapping.up().clone(), mapping.down().clone(), mapping.right().clone(), mapping.left().clone())
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+
+use super::{tile::Tile, utils::Domain};
+
+const WORD_BITS: usize = 64;
+
+//bitset holding tiles 0..size, unlike Bits256Set its size is chosen at runtime
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DynBitSet{
+    words: Vec<u64>,
+    size: usize
+}
+
+impl DynBitSet{
+    pub fn new_empty(size: usize) -> DynBitSet
+    {
+        DynBitSet{words: vec![0; size.div_ceil(WORD_BITS)], size}
+    }
+
+    pub fn new_any(size: usize) -> DynBitSet
+    {
+        let mut bits = DynBitSet{words: vec![u64::MAX; size.div_ceil(WORD_BITS)], size};
+        //bits past size are never set
+        if size % WORD_BITS != 0
+        {
+            *bits.words.last_mut().unwrap() = (1u64 << (size % WORD_BITS)) - 1;
+        }
+        bits
+    }
+
+    pub fn new_from_vec<T: Tile>(size: usize, items: Vec<T>) -> DynBitSet
+    {
+        let mut bits = DynBitSet::new_empty(size);
+        for item in items{
+            bits.insert(item.index());
+        }
+        bits
+    }
+
+    pub fn new_from_hash_set<T: Tile>(size: usize, set: &HashSet<T>) -> DynBitSet
+    {
+        let mut bits = DynBitSet::new_empty(size);
+        for item in set
+        {
+            bits.insert(item.index());
+        }
+        bits
+    }
+
+    pub fn size(&self) -> usize
+    {
+        self.size
+    }
+
+    pub fn intersect_with(& mut self, other: &DynBitSet)
+    {
+        for (word, other) in self.words.iter_mut().zip(&other.words)
+        {
+            *word &= other;
+        }
+    }
+
+    pub fn union_with(& mut self, other: &DynBitSet)
+    {
+        for (word, other) in self.words.iter_mut().zip(&other.words)
+        {
+            *word |= other;
+        }
+    }
+
+    pub fn contains(&self, x: usize) -> bool
+    {
+        x < self.size && self.words[x / WORD_BITS] & (1 << (x % WORD_BITS)) != 0
+    }
+
+    pub fn insert(& mut self, x: usize)
+    {
+        assert!(x < self.size, "tile {} does not fit into set of size {}", x, self.size);
+        self.words[x / WORD_BITS] |= 1 << (x % WORD_BITS);
+    }
+
+    pub fn remove(& mut self, x: usize)
+    {
+        if x < self.size
+        {
+            self.words[x / WORD_BITS] &= !(1 << (x % WORD_BITS));
+        }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn items(&self) -> Vec<usize>
+    {
+        let mut items = Vec::with_capacity(self.len());
+        for (i, &word) in self.words.iter().enumerate()
+        {
+            let mut word = word;
+            while word != 0
+            {
+                items.push(i * WORD_BITS + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
+        }
+        items
+    }
+}
+
+impl Domain for DynBitSet
+{
+    fn intersect_with(& mut self, other: &Self)
+    {
+        DynBitSet::intersect_with(self, other)
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        DynBitSet::is_empty(self)
+    }
+}
@@ -1,4 +1,4 @@
-use std::{rc::Rc, collections::HashMap, cell::RefCell};
+use std::{rc::Rc, collections::{HashMap, BTreeMap}, cell::RefCell};
 
 type FibHeapNodeType = Rc<RefCell<FibHeapNode>>;
 
@@ -126,7 +126,7 @@ impl FibHeap
         }
     }
 
-    fn insert_or_merge(ranked: & mut HashMap<usize, FibHeapNodeType>, node: FibHeapNodeType) -> ()
+    fn insert_or_merge(ranked: & mut BTreeMap<usize, FibHeapNodeType>, node: FibHeapNodeType) -> ()
     {
         if ranked.contains_key(&node.borrow().rank())
         {
@@ -160,7 +160,8 @@ impl FibHeap
 
     fn consolidate(& mut self) -> ()
     {
-        let mut ranked = HashMap::<usize, FibHeapNodeType>::new();
+        //ordered by rank so that ties are broken the same way on every run
+        let mut ranked = BTreeMap::<usize, FibHeapNodeType>::new();
         for node in &self.roots
         {
             if node.borrow().key != self.min.clone().unwrap().borrow().key
@@ -0,0 +1,105 @@
+use std::{collections::{HashSet, VecDeque}, fmt};
+
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+use super::{rules::EMPTY, utils, bits256_set::Bits256Set, topology::Topology, traits::{Contradiction, Grid}};
+
+//cells of a topology are numbered, rules of a tile are indexed by edge label
+#[derive(Clone, Debug)]
+pub struct GraphGrid{}
+
+impl Grid for GraphGrid
+{
+    type Position = usize;
+    type Cells<T: Clone + fmt::Debug> = Vec<T>;
+    type Directions<T> = Vec<T>;
+}
+
+pub type GraphContradiction = Contradiction<u8, GraphGrid>;
+
+//queue propagation over any topology, rules are indexed by tile and then by edge label
+//problem holds one tile per cell, EMPTY cells are to be solved
+pub struct GraphWFC{}
+
+impl GraphWFC
+{
+    pub fn try_solve_seeded(topology: &Topology, problem: &[u8], rules: &[Vec<HashSet<u8>>], seed: u64) -> Result<Vec<u8>, GraphContradiction>
+    {
+        GraphWFC::try_solve_with_rng(topology, problem, rules, &mut StdRng::seed_from_u64(seed))
+    }
+
+    //fixed tiles without rules are reported as a contradiction of their own cell
+    pub fn try_solve_with_rng(topology: &Topology, problem: &[u8], rules: &[Vec<HashSet<u8>>], rng: &mut dyn RngCore) -> Result<Vec<u8>, GraphContradiction>
+    {
+        assert_eq!(problem.len(), topology.cells(), "problem needs one tile per cell");
+        if let Some(cell) = problem.iter().position(|&tile| tile != EMPTY && tile as usize >= rules.len())
+        {
+            let mut partial = problem.to_vec();
+            partial[cell] = EMPTY;
+            return Err(Contradiction{position: cell, neighbor: cell, step: 0, partial});
+        }
+        let rules_internal: Vec<Vec<Bits256Set>> = rules.iter()
+            .map(|label_rules| (0..topology.labels()).map(|label| label_rules.get(label).map_or(Bits256Set::new_empty(), Bits256Set::new_from_hash_set)).collect())
+            .collect();
+        //tiles which rules allow but which have no rules of their own are never chosen
+        let with_rules = Bits256Set::new_from_vec((0..rules.len().min(u8::MAX as usize + 1)).map(|tile| tile as u8).collect());
+        let any_tile = Bits256Set::new_intersection(vec![Bits256Set::new_sum(rules_internal.iter().flatten().cloned().collect()), with_rules]);
+        let mut solution: Vec<Bits256Set> = problem.iter()
+            .map(|&tile| if tile == EMPTY { any_tile } else { Bits256Set::new_from_vec(vec![tile]) })
+            .collect();
+        let mut to_propagate = VecDeque::from_iter(0..topology.cells());
+        GraphWFC::propagate(topology, &mut solution, &rules_internal, &mut to_propagate, 0)?;
+        let mut step = 0;
+        while let Some(cell) = GraphWFC::find_minimal(&solution) {
+            //minimal found setting it randomly
+            solution[cell] = Bits256Set::new_from_vec(vec![utils::get_random_with(rng, solution[cell].items())]);
+            step += 1;
+            let mut to_propagate = VecDeque::from_iter(topology.outgoing(cell).iter().map(|&(_, to)| to));
+            GraphWFC::propagate(topology, &mut solution, &rules_internal, &mut to_propagate, step)?;
+        }
+        Ok(GraphWFC::format_solution(&solution))
+    }
+
+    fn propagate(topology: &Topology, solution: & mut [Bits256Set], rules: &[Vec<Bits256Set>], to_propagate: & mut VecDeque<usize>, step: usize) -> Result<(), GraphContradiction>
+    {
+        while let Some(cell) = to_propagate.pop_front() {
+            if solution[cell].len() > 1
+            {
+                //cells without incoming edges keep their candidates
+                let updated = Bits256Set::new_intersection(GraphWFC::neighbor_constraints(topology, cell, solution, rules).into_iter().map(|(constraint, _)| constraint).chain([solution[cell]]).collect());
+                if updated != solution[cell] {
+                    solution[cell] = updated;
+                    if updated.is_empty()
+                    {
+                        let neighbor = utils::wiping_neighbor(GraphWFC::neighbor_constraints(topology, cell, solution, rules), cell);
+                        return Err(Contradiction{position: cell, neighbor, step, partial: GraphWFC::format_solution(solution)});
+                    }
+                    //propagation changed state, need to continue propagation
+                    to_propagate.extend(topology.outgoing(cell).iter().map(|&(_, to)| to));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_minimal(solution: &[Bits256Set]) -> Option<usize>
+    {
+        (0..solution.len())
+            .filter(|&cell| solution[cell].len() > 1)
+            .min_by_key(|&cell| solution[cell].len())
+    }
+
+    //constraint of every incoming edge together with the cell it comes from
+    fn neighbor_constraints(topology: &Topology, cell: usize, solution: &[Bits256Set], rules: &[Vec<Bits256Set>]) -> Vec<(Bits256Set, usize)>
+    {
+        topology.incoming(cell).iter().map(|&(label, from)| {
+            (Bits256Set::new_sum(solution[from].items().iter().map(|&tile| rules[tile as usize][label]).collect()), from)
+        }).collect()
+    }
+
+
+    fn format_solution(solution: &[Bits256Set]) -> Vec<u8>
+    {
+        solution.iter().map(|candidates| candidates.items().into_iter().next().unwrap_or(EMPTY)).collect()
+    }
+}
@@ -5,9 +5,36 @@ pub mod queueprop;
 pub mod stackprop;
 pub mod queueprop_bitarrayset;
 pub mod queueprop_bitarrayset_fibheap;
+pub mod queueprop_dynset;
+pub mod ac4;
+pub mod backtracking;
+pub mod graph;
+pub mod bounded;
+pub mod budget;
+pub mod chunks;
+pub mod counts;
+pub mod connectivity;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod weighted;
+pub mod stepper;
+pub mod snapshot;
+pub mod overlapping;
+pub mod symmetry;
+pub mod tileset;
+pub mod tileset_toml;
+pub mod tileset_xml;
+pub mod boundary;
+pub mod topology;
 pub mod direction_mapping;
+pub mod direction_mapping2d;
 pub mod utils;
 pub mod rules;
+pub mod rules_builder;
+pub mod rules_check;
 pub mod vec3d;
-mod bits256_set;
+pub mod vec2d;
+pub mod bits256_set;
+pub mod dyn_bitset;
+pub mod tile;
 mod fib_heap;
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+use super::{direction_mapping::DirectionMapping, rules::EMPTY, dyn_bitset::DynBitSet, queueprop_dynset::QueuePropDynSet, weighted::CellSelection, vec3d::{Vec3D, PosIter3D}, traits::Contradiction, symmetry::{self, Symmetry}};
+
+//pattern ids are u16, one id is taken by the border pattern
+pub const MAX_PATTERNS: usize = u16::MAX as usize;
+
+pub struct OverlappingModel{
+    n: usize,
+    patterns: Vec<Vec3D<u8>>,
+    weights: Vec<f64>,
+    rules: Vec<DirectionMapping<HashSet<u16>>>
+}
+
+impl OverlappingModel
+{
+    //returns None if the sample contains more than MAX_PATTERNS distinct patterns
+    pub fn new(sample: &Vec3D<u8>, n: usize, symmetry: Symmetry) -> Option<OverlappingModel>
+    {
+        let mut patterns = Vec::<Vec3D<u8>>::new();
+        let mut weights = Vec::<f64>::new();
+        let mut index = HashMap::<Vec3D<u8>, usize>::new();
+        let transforms = symmetry::transforms(symmetry);
+        for (x, y, z) in PosIter3D::new(sample)
+        {
+            if x + n > sample.x_size || y + n > sample.y_size || z + n > sample.z_size
+            {
+                continue;
+            }
+            let pattern = OverlappingModel::extract(sample, (x, y, z), n);
+            for transform in &transforms
+            {
+                let variant = symmetry::transform_voxels(&pattern, transform);
+                match index.get(&variant) {
+                    Some(&i) => weights[i] += 1.0,
+                    None => {
+                        index.insert(variant.clone(), patterns.len());
+                        patterns.push(variant);
+                        weights.push(1.0);
+                    }
+                }
+            }
+        }
+        if patterns.len() > MAX_PATTERNS
+        {
+            return None;
+        }
+        let mut rules: Vec<DirectionMapping<HashSet<u16>>> = patterns.iter().map(|a| DirectionMapping::from_fn(|offset| {
+            patterns.iter()
+                .enumerate()
+                .filter(|(_, b)| OverlappingModel::agrees(a, b, offset))
+                .map(|(i, _)| i as u16)
+                .collect()
+        })).collect();
+        //border pattern accepts every pattern next to it and is never chosen
+        let any: HashSet<u16> = (0..patterns.len() as u16).collect();
+        rules.push(DirectionMapping::from_fn(|_| any.clone()));
+        weights.push(0.0);
+        Some(OverlappingModel{n, patterns, weights, rules})
+    }
+
+    pub fn n(&self) -> usize
+    {
+        self.n
+    }
+
+    pub fn patterns(&self) -> &Vec<Vec3D<u8>>
+    {
+        &self.patterns
+    }
+
+    //rules and weights are indexed by pattern, last entry is the border pattern
+    pub fn rules(&self) -> &Vec<DirectionMapping<HashSet<u16>>>
+    {
+        &self.rules
+    }
+
+    pub fn weights(&self) -> &Vec<f64>
+    {
+        &self.weights
+    }
+
+    pub fn try_solve_seeded(&self, x_size: usize, y_size: usize, z_size: usize, seed: u64) -> Result<Vec3D<u8>, Contradiction>
+    {
+        self.try_solve_with_rng(x_size, y_size, z_size, &mut StdRng::seed_from_u64(seed))
+    }
+
+    //positions in a contradiction count the one cell border around the output
+    pub fn try_solve_with_rng(&self, x_size: usize, y_size: usize, z_size: usize, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        let border = self.patterns.len();
+        let rules = QueuePropDynSet::prepare_rules(&self.rules);
+        let mut wave = Vec3D::with_borders(
+            x_size + 2, y_size + 2, z_size + 2,
+            DynBitSet::new_from_vec(rules.len(), (0..border as u16).collect()),
+            DynBitSet::new_from_vec(rules.len(), vec![border as u16]));
+        //patterns without any match in some direction are removed before anything is collapsed
+        let mut to_propagate = VecDeque::from_iter(PosIter3D::new_no_border(&wave));
+        let result = QueuePropDynSet::propagate::<u16>(&mut wave, &rules, &mut to_propagate, 0)
+            .and_then(|_| QueuePropDynSet::collapse(&mut wave, &rules, &self.weights, CellSelection::Entropy, rng));
+        match result {
+            Ok(()) => Ok(self.decode(&wave)),
+            Err(contradiction) => Err(Contradiction::new(contradiction.position, contradiction.neighbor, contradiction.step, self.decode(&wave)))
+        }
+    }
+
+    //every cell takes the voxel at the origin of its pattern, undecided cells are EMPTY
+    fn decode(&self, wave: &Vec3D<DynBitSet>) -> Vec3D<u8>
+    {
+        let mut output = Vec3D::new(wave.x_size - 2, wave.y_size - 2, wave.z_size - 2, EMPTY);
+        for (x, y, z) in PosIter3D::new(&output)
+        {
+            let candidates = wave.get_ref(x + 1, y + 1, z + 1);
+            if candidates.len() == 1
+            {
+                output.set(x, y, z, self.patterns[candidates.items()[0]].get(0, 0, 0));
+            }
+        }
+        output
+    }
+
+    fn extract(sample: &Vec3D<u8>, (x, y, z): (usize, usize, usize), n: usize) -> Vec3D<u8>
+    {
+        let mut pattern = Vec3D::new(n, n, n, 0);
+        for (i, j, k) in PosIter3D::new(&pattern)
+        {
+            pattern.set(i, j, k, sample.get(x + i, y + j, z + k));
+        }
+        pattern
+    }
+
+    //whether b placed at offset from a matches a on their overlap
+    fn agrees(a: &Vec3D<u8>, b: &Vec3D<u8>, (dx, dy, dz): (isize, isize, isize)) -> bool
+    {
+        let n = a.x_size as isize;
+        PosIter3D::new(a).all(|(x, y, z)| {
+            let (bx, by, bz) = (x as isize - dx, y as isize - dy, z as isize - dz);
+            if bx < 0 || by < 0 || bz < 0 || bx >= n || by >= n || bz >= n
+            {
+                return true;
+            }
+            a.get(x, y, z) == b.get(bx as usize, by as usize, bz as usize)
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashSet, marker::PhantomData, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, thread};
+
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+
+use super::{direction_mapping::DirectionMapping, vec3d::Vec3D, traits::{WFC, Contradiction}, budget::{Metering, SolveError, StopReason}};
+
+pub const DEFAULT_MAX_ATTEMPTS: usize = 64;
+
+//runs independent attempts of solver T on all cores, attempt i is seeded with seed + i
+//result is the successful attempt with the lowest index, so it does not depend on thread timing
+pub struct Parallel<T: WFC>{
+    solver: PhantomData<T>
+}
+
+impl<T: WFC> Parallel<T>
+{
+    //if every attempt fails the contradiction of the first attempt is returned, at least one attempt is always made
+    pub fn try_solve_restarts(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, seed: u64, max_attempts: usize, threads: usize) -> Result<Vec3D<u8>, Contradiction>
+    {
+        Parallel::<T>::restarts(max_attempts, threads, |attempt| T::try_solve_seeded(map, rules, seed.wrapping_add(attempt as u64)))
+    }
+
+    //every attempt spends the same meter, so once it stops the attempts still running stop as well
+    pub fn try_solve_restarts_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, seed: u64, max_attempts: usize, threads: usize, meter: &mut M) -> Result<Vec3D<u8>, SolveError>
+    {
+        let shared = Mutex::new(meter);
+        Parallel::<T>::restarts(max_attempts, threads, |attempt| {
+            T::try_solve_metered(map, rules, &mut SharedMeter{meter: &shared}, &mut StdRng::seed_from_u64(seed.wrapping_add(attempt as u64)))
+        })
+    }
+
+    fn restarts<E: Send, F: Fn(usize) -> Result<Vec3D<u8>, E> + Sync>(max_attempts: usize, threads: usize, solve: F) -> Result<Vec3D<u8>, E>
+    {
+        let max_attempts = max_attempts.max(1);
+        let next_attempt = AtomicUsize::new(0);
+        //lowest successful attempt found so far, attempts after it are not started
+        let best = AtomicUsize::new(usize::MAX);
+        let results = Mutex::new(Vec::<(usize, Result<Vec3D<u8>, E>)>::new());
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1)
+            {
+                scope.spawn(|| loop {
+                    let attempt = next_attempt.fetch_add(1, Ordering::SeqCst);
+                    if attempt >= max_attempts || attempt > best.load(Ordering::SeqCst)
+                    {
+                        break;
+                    }
+                    let result = solve(attempt);
+                    if result.is_ok()
+                    {
+                        best.fetch_min(attempt, Ordering::SeqCst);
+                    }
+                    results.lock().unwrap().push((attempt, result));
+                });
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(attempt, _)| *attempt);
+        let chosen = results.iter().position(|(_, result)| result.is_ok()).unwrap_or(0);
+        results.swap_remove(chosen).1
+    }
+
+    pub fn available_threads() -> usize
+    {
+        thread::available_parallelism().map_or(1, |threads| threads.get())
+    }
+}
+
+//meter of restarts, locked for every check
+struct SharedMeter<'a, M: Metering>{
+    meter: &'a Mutex<&'a mut M>
+}
+
+impl<M: Metering> Metering for SharedMeter<'_, M>
+{
+    fn collapse(&mut self) -> Result<(), StopReason>
+    {
+        self.meter.lock().unwrap().collapse()
+    }
+
+    fn propagation(&mut self) -> Result<(), StopReason>
+    {
+        self.meter.lock().unwrap().propagation()
+    }
+}
+
+impl<T: WFC> WFC for Parallel<T>{
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        Parallel::<T>::try_solve_restarts_metered(map, rules, rng.gen(), DEFAULT_MAX_ATTEMPTS, Parallel::<T>::available_threads(), meter)
+    }
+
+    //unmetered restarts do not have to share a meter
+    fn try_solve_with_rng(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        Parallel::<T>::try_solve_restarts(map, rules, rng.gen(), DEFAULT_MAX_ATTEMPTS, Parallel::<T>::available_threads())
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::{HashSet, VecDeque};
 
-use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, vec3d::{Vec3D, PosIter3D}, traits::WFC};
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, vec3d::{Vec3D, PosIter3D, neighbors}, traits::{WFC, Contradiction}, budget::{Metering, SolveError}};
 
 pub struct QueueProp{}
 
@@ -8,11 +10,11 @@ impl QueueProp
 {
     fn vec_bool_set_from_set(set: &HashSet<u8>) -> Vec<bool>
     {
-        (0..u8::MAX).into_iter().map(|x| set.contains(&x)).collect()
+        (0..=u8::MAX).map(|x| set.contains(&x)).collect()
     }
     fn vec_bool_set_from(collection: &[u8]) -> Vec<bool>
     {
-        (0..u8::MAX).into_iter().map(|x| collection.contains(&x)).collect()
+        (0..=u8::MAX).map(|x| collection.contains(&x)).collect()
     }
     fn prepare_rules(rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec<DirectionMapping<Vec<bool>>>
     {
@@ -25,7 +27,7 @@ impl QueueProp
             Self::vec_bool_set_from_set(&dir_rules.back()),
         )).collect()
     }
-    fn prepare_map(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec3D<Vec<bool>>
+    fn prepare_map<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Vec<bool>>>, meter: & mut M) -> Result<Vec3D<Vec<bool>>, SolveError>
     {
         let mut to_propagate = VecDeque::<(usize, usize, usize)>::new();
         let mut solution = Vec3D::<Vec<bool>>::new(map.x_size, map.y_size, map.z_size, Self::vec_bool_set_from_set(&rules::get_any_tile()));
@@ -33,21 +35,33 @@ impl QueueProp
             let val = map.get(x, y, z);
             if val != EMPTY{
                 solution.set(x, y, z, Self::vec_bool_set_from(&[val]));
-                to_propagate.push_back((x, y, z));
+                if !map.is_border(x, y, z){
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
             }
         }
-        Self::propagate(&mut solution, rules, & mut to_propagate);
-        solution
+        Self::propagate(&mut solution, rules, & mut to_propagate, 0, meter)?;
+        Ok(solution)
     }
 
-    fn propagate(solution: & mut Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>, to_propagate: & mut VecDeque<(usize,usize,usize)>) -> ()
+    fn propagate<M: Metering>(solution: & mut Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>, to_propagate: & mut VecDeque<(usize,usize,usize)>, step: usize, meter: & mut M) -> Result<(), SolveError>
     {
         while !to_propagate.is_empty(){
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: Self::format_partial(solution)});
+            }
             let (x, y, z) = to_propagate.pop_front().unwrap();
             if solution.get(x, y, z).iter().filter(|&&x| x).count() > 1
             {
                 let updated = Self::legal_tiles(x, y, z, &solution, rules);
                 if updated != solution.get(x, y, z) {
+                    if !updated.contains(&true)
+                    {
+                        solution.set(x, y, z, updated);
+                        let neighbor = utils::wiping_neighbor(Self::neighbor_constraints(x, y, z, solution, rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, step, Self::format_solution(solution)).into());
+                    }
                     solution.set(x, y, z, updated);
                     //propagation changed state, need to continue propagation
                     to_propagate.push_back((x+1,y,z));
@@ -59,6 +73,7 @@ impl QueueProp
                 }
             }
         }
+        Ok(())
     }
 
     fn find_minimal(solution: &Vec3D<Vec<bool>>) -> Option<(usize,usize,usize, Vec<bool>)>
@@ -100,66 +115,71 @@ impl QueueProp
 
     fn legal_tiles(x: usize, y: usize, z: usize, solution: &Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec<bool>
     {
-        let w: Vec<Vec<bool>> = vec![
+        QueueProp::neighbor_constraints(x, y, z, solution, rules)
+            .iter()
+            .fold(vec![true; u8::MAX as usize + 1], |acc, x| acc.iter()
+                                                            .zip(x)
+                                                            .map(|(&a,&b)| a && b)
+                                                            .collect())
+    }
+
+    fn neighbor_constraints(x: usize, y: usize, z: usize, solution: &Vec3D<Vec<bool>>, rules: &Vec<DirectionMapping<Vec<bool>>>) -> Vec<Vec<bool>>
+    {
+        vec![
             solution.get(x, y + 1, z)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].down())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
                                                                         .collect()),
             solution.get(x, y - 1, z)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].up())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
                                                                         .collect()),
             solution.get(x - 1, y, z)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].left())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
                                                                         .collect()),
             solution.get(x + 1, y, z)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].right())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
                                                                         .collect()),
             solution.get(x, y, z - 1)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].front())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
                                                                         .collect()),
             solution.get(x, y, z + 1)
                .iter()
-               .zip(0..u8::MAX)
+               .zip(0..=u8::MAX)
                .filter_map(|(b, idx)| match b{true => Some(idx), false => None})
                .map(|idx| rules[idx as usize].back())
-               .fold(vec![false;u8::MAX as usize],|acc:Vec<bool>, b| acc.iter()
+               .fold(vec![false;u8::MAX as usize + 1],|acc:Vec<bool>, b| acc.iter()
                                                                         .zip(b)
                                                                         .map(|(&a,&b)| a||b)
-                                                                        .collect())];
-
-        w.iter().fold(vec![true; u8::MAX as usize], |acc, x| acc.iter()
-                                                                                               .zip(x)
-                                                                                               .map(|(&a,&b)| a && b)
-                                                                                               .collect())
+                                                                        .collect())]
     }
 
     fn format_solution(solution: &Vec3D<Vec<bool>>) -> Vec3D<u8>
@@ -167,7 +187,7 @@ impl QueueProp
         let mut ret = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, EMPTY);
         for (x, y, z) in PosIter3D::new(&solution)
         {
-            ret.set(x, y, z, solution.get(x, y, z).iter().zip(0..u8::MAX).filter_map(|(&egz, x)|
+            ret.set(x, y, z, solution.get(x, y, z).iter().zip(0..=u8::MAX).filter_map(|(&egz, x)|
                 {
                     if egz
                     {
@@ -180,22 +200,41 @@ impl QueueProp
         }
         ret
     }
+
+    //decided cells hold their tile and the rest is EMPTY
+    fn format_partial(solution: &Vec3D<Vec<bool>>) -> Vec3D<u8>
+    {
+        let mut ret = Self::format_solution(solution);
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            if solution.get_ref(x, y, z).iter().filter(|&&x| x).count() > 1
+            {
+                ret.set(x, y, z, EMPTY);
+            }
+        }
+        ret
+    }
 }
 
 impl WFC for QueueProp{
-    fn solve(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
     {
         let rules = &Self::prepare_rules(rules);
         //prepare format
-        let mut solution = Self::prepare_map(map, rules);
+        let mut solution = Self::prepare_map(map, rules, meter)?;
+        let mut step = 0;
         loop {
             //find minimal non zero entropy
             let minimal = QueueProp::find_minimal(&mut solution);
             match minimal {
                 Some((x,y, z, current)) => {
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: Self::format_partial(&solution)});
+                    }
                     //minimal found setting it randomly
-                    solution.set(x, y, z, Self::vec_bool_set_from(&[utils::get_random(current.iter()
-                                                                                                                  .zip(0..u8::MAX)
+                    solution.set(x, y, z, Self::vec_bool_set_from(&[utils::get_random_with(rng, current.iter()
+                                                                                                                  .zip(0..=u8::MAX)
                                                                                                                   .filter_map(|(&egz,x)|{
                                                                                                                     if egz{
                                                                                                                         Some(x)
@@ -204,6 +243,7 @@ impl WFC for QueueProp{
                                                                                                                         None
                                                                                                                     }
                                                                                                                   }))]));
+                    step += 1;
                     let mut to_propagate = VecDeque::<(usize,usize,usize)>::new();
                     to_propagate.push_back((x+1,y,z));
                     to_propagate.push_back((x-1,y,z));
@@ -211,11 +251,11 @@ impl WFC for QueueProp{
                     to_propagate.push_back((x,y-1,z));
                     to_propagate.push_back((x,y,z+1));
                     to_propagate.push_back((x,y,z-1));
-                    QueueProp::propagate(&mut solution, rules, &mut to_propagate);
+                    QueueProp::propagate(&mut solution, rules, &mut to_propagate, step, meter)?;
                 },
                 None => {
                     //nothing left to be collapsed, returning solution
-                    return QueueProp::format_solution(&solution);
+                    return Ok(QueueProp::format_solution(&solution));
                 }
             }
         }
@@ -1,148 +1,366 @@
-use std::collections::{HashSet, VecDeque};
+use std::{collections::{HashSet, VecDeque}, marker::PhantomData};
 
-use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, bits256_set::Bits256Set, vec3d::{Vec3D, PosIter3D}, traits::WFC};
+use rand::RngCore;
 
-pub struct QueuePropBitArraySet{}
+use super::{direction_mapping::DirectionMapping, rules::EMPTY, utils, bits256_set::Bits256Set, vec3d::{Vec3D, PosIter3D, neighbors}, traits::{WFC, Contradiction, Grid, Grid2D, Grid3D, Lattice}, budget::{Metering, Unmetered, SolveError}, counts::{self, CountConstraint}, connectivity::{self, Connectivity}};
 
-impl QueuePropBitArraySet
+//choices of constrained solves which can be undone and number of undone choices before giving up
+pub const CONSTRAINED_MAX_DECISIONS: usize = 16;
+pub const CONSTRAINED_MAX_BACKTRACKS: usize = 1_000;
+
+//wave along with cells which have to be propagated
+pub(crate) type Wave<G = Grid3D> = (<G as Grid>::Cells<Bits256Set>, VecDeque<<G as Grid>::Position>);
+
+//queue propagation over bit sets on any lattice, every cell of a grid is stored once without extra layers
+pub struct QueuePropBitArraySetOn<G: Lattice>{
+    grid: PhantomData<G>
+}
+
+pub type QueuePropBitArraySet = QueuePropBitArraySetOn<Grid3D>;
+pub type QueuePropBitArraySet2D = QueuePropBitArraySetOn<Grid2D>;
+
+impl<G: Lattice> QueuePropBitArraySetOn<G>
 {
-    fn prepare_rules(rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec<DirectionMapping<Bits256Set>>
+    pub(crate) fn prepare_rules(rules: &[G::Directions<HashSet<u8>>]) -> Vec<G::Directions<Bits256Set>>
     {
-        rules.iter().map(|dir_rules| DirectionMapping::new(
-            Bits256Set::new_from_hash_set(dir_rules.up()),
-            Bits256Set::new_from_hash_set(dir_rules.down()),
-            Bits256Set::new_from_hash_set(dir_rules.right()),
-            Bits256Set::new_from_hash_set(dir_rules.left()),
-            Bits256Set::new_from_hash_set(dir_rules.front()),
-            Bits256Set::new_from_hash_set(dir_rules.back())
-        )).collect()
+        rules.iter().map(|dir_rules| G::map_directions(dir_rules, Bits256Set::new_from_hash_set)).collect()
     }
-    fn prepare_map(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Bits256Set>>) -> Vec3D<Bits256Set>
+    pub(crate) fn prepare_map(map: &G::Cells<u8>, rules: &[G::Directions<Bits256Set>]) -> Result<G::Cells<Bits256Set>, Contradiction<u8, G>>
     {
-        let mut to_propagate = VecDeque::<(usize, usize, usize)>::new();
-        let mut solution = Vec3D::<Bits256Set>::new(map.x_size, map.y_size, map.z_size, Bits256Set::new_from_hash_set(&rules::get_any_tile()));
-        for (x, y, z) in PosIter3D::new(&solution){
-            let val = map.get(x, y, z);
+        Self::prepare_map_metered(map, rules, &mut Unmetered{}).map_err(SolveError::into_contradiction)
+    }
+
+    pub(crate) fn prepare_map_metered<M: Metering>(map: &G::Cells<u8>, rules: &[G::Directions<Bits256Set>], meter: & mut M) -> Result<G::Cells<Bits256Set>, SolveError<G>>
+    {
+        Self::check_map(map, rules)?;
+        let (mut solution, mut to_propagate) = Self::prepare_wave(map, rules);
+        Self::propagate_metered(&mut solution, rules, & mut to_propagate, 0, meter)?;
+        Ok(solution)
+    }
+
+    //fixed tiles need rules, inside the border they also have to be allowed by some rule
+    pub(crate) fn check_map(map: &G::Cells<u8>, rules: &[G::Directions<Bits256Set>]) -> Result<(), Contradiction<u8, G>>
+    {
+        let any_tile = Self::any_tile(rules);
+        for position in G::positions(map)
+        {
+            let val = G::get(map, position);
+            if val != EMPTY && (val as usize >= rules.len() || (!G::is_border(map, position) && !any_tile.contains(val)))
+            {
+                let mut partial = map.clone();
+                G::set(&mut partial, position, EMPTY);
+                return Err(Contradiction{position, neighbor: position, step: 0, partial});
+            }
+        }
+        Ok(())
+    }
+
+    //wave with fixed tiles set, along with cells which have to be propagated
+    pub(crate) fn prepare_wave(map: &G::Cells<u8>, rules: &[G::Directions<Bits256Set>]) -> Wave<G>
+    {
+        let mut to_propagate = VecDeque::new();
+        let mut solution = G::new_cells(map, Self::any_tile(rules));
+        for position in G::positions(map){
+            let val = G::get(map, position);
             if val != EMPTY{
-                solution.set(x, y, z, Bits256Set::new_from_vec(Vec::from([val])));
-                to_propagate.push_back((x, y, z));
+                G::set(&mut solution, position, Bits256Set::new_from_vec(Vec::from([val])));
+                if !G::is_border(map, position){
+                    to_propagate.extend(G::neighbors(position));
+                }
             }
         }
-        QueuePropBitArraySet::propagate(&mut solution, rules, & mut to_propagate);
-        solution
+        (solution, to_propagate)
     }
 
-    fn propagate(solution: & mut Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>, to_propagate: & mut VecDeque<(usize,usize,usize)>) -> ()
+    //every tile which some rule allows, for pipe rules it is the same as rules::get_any_tile
+    pub(crate) fn any_tile(rules: &[G::Directions<Bits256Set>]) -> Bits256Set
+    {
+        Bits256Set::new_sum(rules.iter().flat_map(|dir_rules| (0..G::DIRECTIONS).map(|direction| *G::direction(dir_rules, direction))).collect())
+    }
+
+    pub(crate) fn propagate(solution: & mut G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>], to_propagate: & mut VecDeque<G::Position>, step: usize) -> Result<(), Contradiction<u8, G>>
+    {
+        Self::propagate_metered(solution, rules, to_propagate, step, &mut Unmetered{}).map_err(SolveError::into_contradiction)
+    }
+
+    pub(crate) fn propagate_metered<M: Metering>(solution: & mut G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>], to_propagate: & mut VecDeque<G::Position>, step: usize, meter: & mut M) -> Result<(), SolveError<G>>
     {
         while !to_propagate.is_empty(){
-            let (x, y, z) = to_propagate.pop_front().unwrap();
-            if solution.get(x, y, z).len() > 1
+            if let Err(reason) = meter.propagation()
             {
-                let updated = QueuePropBitArraySet::legal_tiles(x, y, z, &solution, rules);
-                if updated != solution.get(x, y, z) {
-                    solution.set(x, y, z, updated);
+                return Err(SolveError::Stopped{reason, step, partial: Self::format_partial(solution)});
+            }
+            let position = to_propagate.pop_front().unwrap();
+            let current = G::get(solution, position);
+            if current.len() > 1
+            {
+                //cells never get back tiles they lost, such as ones outside of their candidates
+                let updated = Bits256Set::new_intersection(vec![Self::legal_tiles(position, solution, rules), current]);
+                if updated != current {
+                    G::set(solution, position, updated);
+                    if updated.is_empty()
+                    {
+                        let neighbor = utils::wiping_neighbor(Self::neighbor_constraints(position, solution, rules).into_iter().zip(G::neighbors(position)), position);
+                        return Err(Contradiction{position, neighbor, step, partial: Self::format_solution(solution)}.into());
+                    }
                     //propagation changed state, need to continue propagation
-                    to_propagate.push_back((x+1,y,z));
-                    to_propagate.push_back((x-1,y,z));
-                    to_propagate.push_back((x,y+1,z));
-                    to_propagate.push_back((x,y-1,z));
-                    to_propagate.push_back((x,y,z+1));
-                    to_propagate.push_back((x,y,z-1));
+                    to_propagate.extend(G::neighbors(position));
                 }
             }
         }
+        Ok(())
     }
 
-    fn find_minimal(solution: & mut Vec3D<Bits256Set>) -> Option<(usize,usize,usize, Bits256Set)>
+    //undecided cell with the fewest candidates, the first one in position order on ties
+    pub(crate) fn find_minimal(solution: &G::Cells<Bits256Set>) -> Option<(G::Position, Bits256Set)>
     {
-        PosIter3D::new(solution)
-        .map(|(x,y,z)| Some((x, y, z, solution.get(x, y, z))))
-        .fold(None, |acc, x| {
-            match acc
+        let mut minimal: Option<(G::Position, Bits256Set)> = None;
+        for position in G::positions(solution)
+        {
+            let current = G::get(solution, position);
+            if current.len() > 1 && minimal.map_or(true, |(_, best)| current.len() < best.len())
             {
-                Some(a) => {
-                    let x = x.unwrap();
-                    let next_len = x.3.len();
-                    let this_len = a.3.len();
-                    if  next_len > 1 && next_len < this_len
-                    {
-                        Some(x)
-                    }
-                    else
-                    {
-                        Some(a)
-                    }
-                },
-                None =>
-                {
-                    let x = x.unwrap();
-                    let next_len = x.3.len();
-                    if next_len > 1
-                    {
-                        Some(x)
-                    }
-                    else
-                    {
-                        None
-                    }
-                }
+                minimal = Some((position, current));
+            }
+        }
+        minimal
+    }
+
+    pub(crate) fn legal_tiles(position: G::Position, map: &G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>]) -> Bits256Set
+    {
+        Bits256Set::new_intersection(Self::neighbor_constraints(position, map, rules))
+    }
+
+    //tiles which each neighbor allows in the cell, in the order of neighbors
+    pub(crate) fn neighbor_constraints(position: G::Position, map: &G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>]) -> Vec<Bits256Set>
+    {
+        G::neighbors(position).into_iter().enumerate().map(|(direction, neighbor)| {
+            let constraining = G::constraining_direction(direction);
+            Bits256Set::new_sum(G::get(map, neighbor).items().iter().map(|&s| *G::direction(&rules[s as usize], constraining)).collect())
+        }).collect()
+    }
+
+    //collapses every undecided cell of the wave
+    pub(crate) fn collapse(solution: & mut G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>], rng: &mut dyn RngCore) -> Result<(), Contradiction<u8, G>>
+    {
+        Self::collapse_metered(solution, rules, rng, &mut Unmetered{}).map_err(SolveError::into_contradiction)
+    }
+
+    pub(crate) fn collapse_metered<M: Metering>(solution: & mut G::Cells<Bits256Set>, rules: &[G::Directions<Bits256Set>], rng: &mut dyn RngCore, meter: & mut M) -> Result<(), SolveError<G>>
+    {
+        let mut step = 0;
+        //find minimal non zero entropy
+        while let Some((position, current)) = Self::find_minimal(solution) {
+            if let Err(reason) = meter.collapse()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: Self::format_partial(solution)});
             }
-        })
+            //minimal found setting it randomly
+            G::set(solution, position, Bits256Set::new_from_vec(vec![utils::get_random_with(rng, current.items())]));
+            step += 1;
+            let mut to_propagate = VecDeque::from_iter(G::neighbors(position));
+            Self::propagate_metered(solution, rules, &mut to_propagate, step, meter)?;
+        }
+        //nothing left to be collapsed
+        Ok(())
     }
 
-    fn legal_tiles(x: usize, y: usize, z: usize, map: &Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>) -> Bits256Set
+    //decided cells hold their tile and the rest is EMPTY
+    pub(crate) fn format_partial(solution: &G::Cells<Bits256Set>) -> G::Cells<u8>
     {
-        let dirs = vec![
-            Bits256Set::new_sum(map.get(x,y+1, z).items().iter().map(|&s| *rules[s as usize].down()).collect::<Vec<Bits256Set>>()),
-            Bits256Set::new_sum(map.get(x,y-1, z).items().iter().map(|&s| *rules[s as usize].up()).collect::<Vec<Bits256Set>>()),
-            Bits256Set::new_sum(map.get(x-1,y, z).items().iter().map(|&s| *rules[s as usize].left()).collect::<Vec<Bits256Set>>()),
-            Bits256Set::new_sum(map.get(x+1,y, z).items().iter().map(|&s| *rules[s as usize].right()).collect::<Vec<Bits256Set>>()),
-            Bits256Set::new_sum(map.get(x,y, z-1).items().iter().map(|&s| *rules[s as usize].front()).collect::<Vec<Bits256Set>>()),
-            Bits256Set::new_sum(map.get(x,y, z+1).items().iter().map(|&s| *rules[s as usize].back()).collect::<Vec<Bits256Set>>())
-        ];
-        Bits256Set::new_intersection(dirs)
+        let mut ret = G::new_cells(solution, EMPTY);
+        for position in G::positions(solution)
+        {
+            let candidates = G::get(solution, position);
+            if candidates.len() == 1
+            {
+                G::set(&mut ret, position, candidates.items()[0]);
+            }
+        }
+        ret
     }
 
-    fn format_solution(solution: &Vec3D<Bits256Set>) -> Vec3D<u8>
+    pub(crate) fn format_solution(solution: &G::Cells<Bits256Set>) -> G::Cells<u8>
     {
-        let mut ret = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, EMPTY);
-        for (x, y, z) in PosIter3D::new(&solution)
+        let mut ret = G::new_cells(solution, EMPTY);
+        for position in G::positions(solution)
         {
-            ret.set(x, y, z, solution.get(x, y, z).items().into_iter().next().unwrap_or(EMPTY));
+            G::set(&mut ret, position, G::get(solution, position).items().into_iter().next().unwrap_or(EMPTY));
         }
         ret
     }
+}
 
+impl QueuePropBitArraySet
+{
+    //every cell holds the set of tiles it may end up with, cells on the border have to hold a single tile like in other maps
+    pub fn try_solve_candidates(candidates: &Vec3D<HashSet<u8>>, rules: &[DirectionMapping<HashSet<u8>>], rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        let (mut solution, mut to_propagate) = QueuePropBitArraySet::prepare_candidates(candidates, &rules_internal)?;
+        QueuePropBitArraySet::propagate(&mut solution, &rules_internal, &mut to_propagate, 0)?;
+        QueuePropBitArraySet::collapse(&mut solution, &rules_internal, rng)?;
+        Ok(QueuePropBitArraySet::format_solution(&solution))
+    }
 
-}
+    //solves again cells of solution marked in region, everything else stays as it was
+    //only cleared cells are propagated and collapsed, so cost depends on size of the region
+    pub fn try_regenerate(solution: &Vec3D<u8>, region: &Vec3D<bool>, rules: &[DirectionMapping<HashSet<u8>>], rng: &mut dyn RngCore) -> Result<Vec3D<u8>, Contradiction>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        let any_tile = QueuePropBitArraySet::any_tile(&rules_internal);
+        let mut wave = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, Bits256Set::new_empty());
+        let mut cleared = Vec::new();
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            if region.get(x, y, z) && !solution.is_border(x, y, z)
+            {
+                wave.set(x, y, z, any_tile);
+                cleared.push((x, y, z));
+            }
+            else
+            {
+                wave.set(x, y, z, Bits256Set::new_from_vec(vec![solution.get(x, y, z)]));
+            }
+        }
+        let mut to_propagate = VecDeque::from_iter(cleared.iter().cloned());
+        QueuePropBitArraySet::propagate(&mut wave, &rules_internal, &mut to_propagate, 0)?;
+        let mut step = 0;
+        while let Some(&(x, y, z)) = cleared.iter().filter(|&&(x, y, z)| wave.get(x, y, z).len() > 1).min_by_key(|&&(x, y, z)| wave.get(x, y, z).len()) {
+            //minimal found setting it randomly
+            wave.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_random_with(rng, wave.get(x, y, z).items())]));
+            step += 1;
+            let mut to_propagate = VecDeque::from(neighbors((x, y, z)));
+            QueuePropBitArraySet::propagate(&mut wave, &rules_internal, &mut to_propagate, step)?;
+        }
+        Ok(QueuePropBitArraySet::format_solution(&wave))
+    }
 
-impl WFC for QueuePropBitArraySet{
-    fn solve(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>
+    //region of cells with min <= position < max on every axis, for try_regenerate
+    pub fn box_region(solution: &Vec3D<u8>, (min_x, min_y, min_z): (usize, usize, usize), (max_x, max_y, max_z): (usize, usize, usize)) -> Vec3D<bool>
     {
+        let mut region = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, false);
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            region.set(x, y, z, (min_x..max_x).contains(&x) && (min_y..max_y).contains(&y) && (min_z..max_z).contains(&z));
+        }
+        region
+    }
+
+    //interior cells have to respect global tile counts besides rules
+    pub fn try_solve_with_counts(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], constraints: &[CountConstraint], rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        QueuePropBitArraySet::solve_constrained(map, rules,
+            |solution, rules, cause, step| counts::enforce(solution, rules, constraints, cause, step),
+            //groups which reached their maximum are already removed from candidates
+            |solution, current, rng| counts::select(solution, current, constraints, rng),
+            rng)
+    }
 
+    //collapses like collapse while enforce keeps a constraint over the whole grid, select narrows candidates of the collapsed cell
+    //choice which breaks the constraint is undone and its tile ruled out, only the last CONSTRAINED_MAX_DECISIONS choices can be undone
+    //so a solve may still fail where a solution exists, every choice copies the wave and enforce visits every cell
+    fn solve_constrained<E, S>(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], enforce: E, select: S, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    where
+        E: Fn(& mut Vec3D<Bits256Set>, &[DirectionMapping<Bits256Set>], Option<(usize, usize, usize)>, usize) -> Result<(), SolveError>,
+        S: Fn(&Vec3D<Bits256Set>, Bits256Set, &mut dyn RngCore) -> Bits256Set
+    {
         let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
-        let mut solution = QueuePropBitArraySet::prepare_map(map, &rules_internal);
-        loop {
-            //find minimal non zero entropy
-            let minimal = QueuePropBitArraySet::find_minimal(&mut solution);
-            match minimal {
-                Some((x,y, z, current)) => {
-                    //minimal found setting it randomly
-                    solution.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_random(current.items())]));
-                    let mut to_propagate = VecDeque::<(usize,usize,usize)>::new();
-                    to_propagate.push_back((x+1,y,z));
-                    to_propagate.push_back((x-1,y,z));
-                    to_propagate.push_back((x,y+1,z));
-                    to_propagate.push_back((x,y-1,z));
-                    to_propagate.push_back((x,y,z+1));
-                    to_propagate.push_back((x,y,z-1));
-                    QueuePropBitArraySet::propagate(&mut solution, &rules_internal, &mut to_propagate);
-                },
-                None => {
-                    //nothing left to be collapsed, returning solution
-                    return QueuePropBitArraySet::format_solution(&solution);
+        let mut solution = QueuePropBitArraySet::prepare_map(map, &rules_internal)?;
+        enforce(&mut solution, &rules_internal, None, 0)?;
+        //waves before the latest choices, along with the cell and the tile chosen there
+        let mut decisions = VecDeque::<(Vec3D<Bits256Set>, (usize, usize, usize), u8)>::new();
+        let mut step = 0;
+        let mut backtracks = 0;
+        while let Some((position, current)) = QueuePropBitArraySet::find_minimal(&solution) {
+            let candidates = select(&solution, current, rng);
+            let tile = utils::get_random_with(rng, candidates.items());
+            step += 1;
+            decisions.push_back((solution.clone(), position, tile));
+            if decisions.len() > CONSTRAINED_MAX_DECISIONS
+            {
+                decisions.pop_front();
+            }
+            let mut result = QueuePropBitArraySet::restrict(&mut solution, &rules_internal, &enforce, position, Bits256Set::new_from_vec(vec![tile]), step);
+            while let Err(error) = result {
+                //undo the latest choice and rule out the tile which failed there
+                let (previous, position, tile) = match decisions.pop_back() {
+                    Some(decision) if backtracks < CONSTRAINED_MAX_BACKTRACKS => decision,
+                    _ => return Err(error)
+                };
+                backtracks += 1;
+                solution = previous;
+                let mut remaining = solution.get(position.0, position.1, position.2);
+                remaining.remove(tile);
+                result = if remaining.is_empty() {
+                    Err(error)
                 }
+                else {
+                    QueuePropBitArraySet::restrict(&mut solution, &rules_internal, &enforce, position, remaining, step)
+                };
+            }
+        }
+        Ok(QueuePropBitArraySet::format_solution(&solution))
+    }
+
+    fn restrict<E>(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], enforce: &E, (x, y, z): (usize, usize, usize), allowed: Bits256Set, step: usize) -> Result<(), SolveError>
+    where
+        E: Fn(& mut Vec3D<Bits256Set>, &[DirectionMapping<Bits256Set>], Option<(usize, usize, usize)>, usize) -> Result<(), SolveError>
+    {
+        solution.set(x, y, z, allowed);
+        let mut to_propagate = VecDeque::from(neighbors((x, y, z)));
+        QueuePropBitArraySet::propagate(solution, rules, &mut to_propagate, step)?;
+        enforce(solution, rules, Some((x, y, z)), step)
+    }
+
+    //member tiles of connectivity form a single connected component in the solution
+    pub fn try_solve_connected(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], connectivity: &Connectivity, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        QueuePropBitArraySet::solve_constrained(map, rules,
+            |solution, rules, cause, step| connectivity::enforce(solution, rules, connectivity, None, cause, step),
+            |_, current, _| current,
+            rng)
+    }
+
+    //endpoints hold member tiles linked by a path of members, pipe paths of one type follow their direction bits with connectivity::get_pipes_connectivity
+    pub fn try_solve_path(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], connectivity: &Connectivity, endpoints: &[(usize, usize, usize)], rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        QueuePropBitArraySet::solve_constrained(map, rules,
+            |solution, rules, cause, step| connectivity::enforce(solution, rules, connectivity, Some(endpoints), cause, step),
+            |_, current, _| current,
+            rng)
+    }
+
+    //wave restricted to candidates of each cell, along with cells which have to be propagated
+    pub(crate) fn prepare_candidates(candidates: &Vec3D<HashSet<u8>>, rules: &[DirectionMapping<Bits256Set>]) -> Result<Wave, Contradiction>
+    {
+        let any_tile = QueuePropBitArraySet::any_tile(rules);
+        let mut to_propagate = VecDeque::<(usize, usize, usize)>::new();
+        let mut solution = Vec3D::<Bits256Set>::new(candidates.x_size, candidates.y_size, candidates.z_size, any_tile);
+        for (x, y, z) in PosIter3D::new(candidates)
+        {
+            let allowed = Bits256Set::new_intersection(vec![Bits256Set::new_from_hash_set(candidates.get_ref(x, y, z)), any_tile]);
+            solution.set(x, y, z, allowed);
+            if allowed.is_empty()
+            {
+                return Err(Contradiction::new((x, y, z), (x, y, z), 0, QueuePropBitArraySet::format_solution(&solution)));
+            }
+            if allowed != any_tile && !candidates.is_border(x, y, z)
+            {
+                to_propagate.push_back((x, y, z));
+                to_propagate.extend(neighbors((x, y, z)));
             }
         }
+        Ok((solution, to_propagate))
     }
-}
\ No newline at end of file
+}
+
+impl<G: Lattice> WFC<G> for QueuePropBitArraySetOn<G>{
+    fn try_solve_metered<M: Metering>(map: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<G::Cells<u8>, SolveError<G>>
+    {
+        let rules_internal = Self::prepare_rules(rules);
+        let mut solution = Self::prepare_map_metered(map, &rules_internal, meter)?;
+        Self::collapse_metered(&mut solution, &rules_internal, rng, meter)?;
+        Ok(Self::format_solution(&solution))
+    }
+}
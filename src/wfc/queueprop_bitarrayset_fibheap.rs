@@ -1,6 +1,8 @@
 use std::collections::{HashSet, VecDeque};
 
-use super::{direction_mapping::DirectionMapping, rules::{EMPTY, self}, utils, bits256_set::Bits256Set, fib_heap::FibHeap, vec3d::{Vec3D, PosIter3D}, traits::WFC};
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules::EMPTY, queueprop_bitarrayset::QueuePropBitArraySet, utils, bits256_set::Bits256Set, fib_heap::FibHeap, vec3d::{Vec3D, PosIter3D, neighbors}, traits::{WFC, Contradiction}, budget::{Metering, SolveError}};
 
 pub struct QueuePropBitArraySetFibHeap{}
 
@@ -17,15 +19,17 @@ impl QueuePropBitArraySetFibHeap
             Bits256Set::new_from_hash_set(dir_rules.back())
         )).collect()
     }
-    fn prepare_map(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Bits256Set>>, min_heap: & mut FibHeap) -> Vec3D<Bits256Set>
+    fn prepare_map<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<Bits256Set>>, min_heap: & mut FibHeap, meter: & mut M) -> Result<Vec3D<Bits256Set>, SolveError>
     {
         let mut to_propagate = VecDeque::<(usize, usize, usize)>::new();
-        let mut solution = Vec3D::<Bits256Set>::new(map.x_size, map.y_size, map.z_size, Bits256Set::new_from_hash_set(&rules::get_any_tile()));
+        let mut solution = Vec3D::<Bits256Set>::new(map.x_size, map.y_size, map.z_size, QueuePropBitArraySet::any_tile(rules));
         for (x, y, z) in PosIter3D::new(&solution){
             let val = map.get(x, y, z);
             if val != EMPTY{
                 solution.set(x, y, z, Bits256Set::new_from_vec(Vec::from([val])));
-                to_propagate.push_back((x, y, z));
+                if !map.is_border(x, y, z){
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
             }
         }
         for (x, y, z) in PosIter3D::new(map){
@@ -34,8 +38,8 @@ impl QueuePropBitArraySetFibHeap
                 min_heap.insert((x, y, z), solution.get(x, y, z).len() as u8);
             }
         }
-        QueuePropBitArraySetFibHeap::propagate(&mut solution, rules, & mut to_propagate, min_heap);
-        solution
+        QueuePropBitArraySetFibHeap::propagate(&mut solution, rules, & mut to_propagate, min_heap, 0, meter)?;
+        Ok(solution)
     }
 
     fn prepare_heap() -> FibHeap
@@ -43,15 +47,24 @@ impl QueuePropBitArraySetFibHeap
         FibHeap::new()
     }
 
-    fn propagate(solution: & mut Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>, to_propagate: & mut VecDeque<(usize,usize,usize)>, min_heap: & mut FibHeap) -> ()
+    fn propagate<M: Metering>(solution: & mut Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>, to_propagate: & mut VecDeque<(usize,usize,usize)>, min_heap: & mut FibHeap, step: usize, meter: & mut M) -> Result<(), SolveError>
     {
         while !to_propagate.is_empty(){
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(solution)});
+            }
             let (x, y, z) = to_propagate.pop_front().unwrap();
             if solution.get(x, y, z).len() > 1
             {
                 let updated = QueuePropBitArraySetFibHeap::legal_tiles(x, y, z, &solution, rules);
                 if updated != solution.get(x, y, z) {
                     solution.set(x, y, z, updated);
+                    if updated.is_empty()
+                    {
+                        let neighbor = utils::wiping_neighbor(QueuePropBitArraySetFibHeap::neighbor_constraints(x, y, z, solution, rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, step, QueuePropBitArraySetFibHeap::format_solution(solution)).into());
+                    }
                     min_heap.decrease_key((x, y, z), updated.len() as u8);
                     //propagation changed state, need to continue propagation
                     to_propagate.push_back((x+1,y,z));
@@ -63,6 +76,7 @@ impl QueuePropBitArraySetFibHeap
                 }
             }
         }
+        Ok(())
     }
 
     fn find_minimal(heap: & mut FibHeap) -> Option<(usize,usize,usize)>
@@ -72,15 +86,20 @@ impl QueuePropBitArraySetFibHeap
 
     fn legal_tiles(x: usize, y: usize, z: usize, map: &Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>) -> Bits256Set
     {
-        let dirs = vec![
+        Bits256Set::new_intersection(QueuePropBitArraySetFibHeap::neighbor_constraints(x, y, z, map, rules))
+    }
+
+
+    fn neighbor_constraints(x: usize, y: usize, z: usize, map: &Vec3D<Bits256Set>, rules: &Vec<DirectionMapping<Bits256Set>>) -> Vec<Bits256Set>
+    {
+        vec![
             Bits256Set::new_sum(map.get(x,y+1, z).items().iter().map(|&s| *rules[s as usize].down()).collect::<Vec<Bits256Set>>()),
             Bits256Set::new_sum(map.get(x,y-1, z).items().iter().map(|&s| *rules[s as usize].up()).collect::<Vec<Bits256Set>>()),
             Bits256Set::new_sum(map.get(x-1,y, z).items().iter().map(|&s| *rules[s as usize].left()).collect::<Vec<Bits256Set>>()),
             Bits256Set::new_sum(map.get(x+1,y, z).items().iter().map(|&s| *rules[s as usize].right()).collect::<Vec<Bits256Set>>()),
             Bits256Set::new_sum(map.get(x,y, z-1).items().iter().map(|&s| *rules[s as usize].front()).collect::<Vec<Bits256Set>>()),
             Bits256Set::new_sum(map.get(x,y, z+1).items().iter().map(|&s| *rules[s as usize].back()).collect::<Vec<Bits256Set>>())
-        ];
-        Bits256Set::new_intersection(dirs)
+        ]
     }
 
     fn format_solution(solution: &Vec3D<Bits256Set>) -> Vec3D<u8>
@@ -97,20 +116,26 @@ impl QueuePropBitArraySetFibHeap
 }
 
 impl WFC for QueuePropBitArraySetFibHeap{
-    fn solve(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
     {
 
         let rules_internal = QueuePropBitArraySetFibHeap::prepare_rules(rules);
         let mut min_heap = QueuePropBitArraySetFibHeap::prepare_heap();
-        let mut solution = QueuePropBitArraySetFibHeap::prepare_map(map, &rules_internal, & mut min_heap);
+        let mut solution = QueuePropBitArraySetFibHeap::prepare_map(map, &rules_internal, & mut min_heap, meter)?;
+        let mut step = 0;
         loop {
             //find minimal non zero entropy
             let minimal = QueuePropBitArraySetFibHeap::find_minimal(& mut min_heap);
             match minimal {
                 Some((x,y, z)) => {
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(&solution)});
+                    }
                     //minimal found setting it randomly
                     let current = solution.get(x, y, z);
-                    solution.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_random(current.items())]));
+                    solution.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_random_with(rng, current.items())]));
+                    step += 1;
                     let mut to_propagate = VecDeque::<(usize,usize,usize)>::new();
                     to_propagate.push_back((x+1,y,z));
                     to_propagate.push_back((x-1,y,z));
@@ -118,11 +143,11 @@ impl WFC for QueuePropBitArraySetFibHeap{
                     to_propagate.push_back((x,y-1,z));
                     to_propagate.push_back((x,y,z+1));
                     to_propagate.push_back((x,y,z-1));
-                    QueuePropBitArraySetFibHeap::propagate(&mut solution, &rules_internal, &mut to_propagate, & mut min_heap);
+                    QueuePropBitArraySetFibHeap::propagate(&mut solution, &rules_internal, &mut to_propagate, & mut min_heap, step, meter)?;
                 },
                 None => {
                     //nothing left to be collapsed, returning solution
-                    return QueuePropBitArraySetFibHeap::format_solution(&solution);
+                    return Ok(QueuePropBitArraySetFibHeap::format_solution(&solution));
                 }
             }
         }
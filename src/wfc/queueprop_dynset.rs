@@ -0,0 +1,206 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, utils, dyn_bitset::DynBitSet, tile::Tile, weighted::{Weighted, WeightedError, CellSelection}, vec3d::{Vec3D, PosIter3D, neighbors, CONSTRAINING_DIRECTION}, traits::{WFC, Contradiction, Grid3D}, budget::{Metering, Unmetered, SolveError}};
+
+//queue propagation over sets sized by the rules, works for any number of tiles
+//tiles are indexed 0..rules.len(), EMPTY and BORDER of the tile type keep their meaning in the map
+pub struct QueuePropDynSet{}
+
+impl QueuePropDynSet
+{
+    pub fn try_solve_tiles<T: Tile>(map: &Vec3D<T>, rules: &[DirectionMapping<HashSet<T>>], rng: &mut dyn RngCore) -> Result<Vec3D<T>, Contradiction<T>>
+    {
+        QueuePropDynSet::solve(map, rules, &vec![1.0; rules.len()], CellSelection::MinimumRemaining, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)
+    }
+
+    //weights are indexed by tile like rules
+    pub fn try_solve_tiles_weighted<T: Tile>(map: &Vec3D<T>, rules: &[DirectionMapping<HashSet<T>>], weights: &[f64], selection: CellSelection, rng: &mut dyn RngCore) -> Result<Vec3D<T>, WeightedError<T>>
+    {
+        Weighted::check_weights(rules.len(), weights)?;
+        Ok(QueuePropDynSet::solve(map, rules, weights, selection, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)?)
+    }
+
+    fn solve<T: Tile, M: Metering>(map: &Vec3D<T>, rules: &[DirectionMapping<HashSet<T>>], weights: &[f64], selection: CellSelection, meter: & mut M, rng: &mut dyn RngCore) -> Result<Vec3D<T>, SolveError<Grid3D, T>>
+    {
+        let rules_internal = QueuePropDynSet::prepare_rules(rules);
+        let (mut solution, mut to_propagate) = QueuePropDynSet::prepare_wave(map, &rules_internal);
+        QueuePropDynSet::propagate_metered(&mut solution, &rules_internal, &mut to_propagate, 0, meter)?;
+        QueuePropDynSet::collapse_metered(&mut solution, &rules_internal, weights, selection, meter, rng)?;
+        Ok(QueuePropDynSet::format_solution(&solution))
+    }
+
+    pub(crate) fn prepare_rules<T: Tile>(rules: &[DirectionMapping<HashSet<T>>]) -> Vec<DirectionMapping<DynBitSet>>
+    {
+        let size = rules.len();
+        rules.iter().map(|dir_rules| DirectionMapping::new(
+            DynBitSet::new_from_hash_set(size, dir_rules.up()),
+            DynBitSet::new_from_hash_set(size, dir_rules.down()),
+            DynBitSet::new_from_hash_set(size, dir_rules.right()),
+            DynBitSet::new_from_hash_set(size, dir_rules.left()),
+            DynBitSet::new_from_hash_set(size, dir_rules.front()),
+            DynBitSet::new_from_hash_set(size, dir_rules.back())
+        )).collect()
+    }
+
+    //wave with fixed tiles set, along with cells which have to be propagated
+    pub(crate) fn prepare_wave<T: Tile>(map: &Vec3D<T>, rules: &[DirectionMapping<DynBitSet>]) -> (Vec3D<DynBitSet>, VecDeque<(usize, usize, usize)>)
+    {
+        let mut to_propagate = VecDeque::<(usize, usize, usize)>::new();
+        let mut solution = Vec3D::new(map.x_size, map.y_size, map.z_size, QueuePropDynSet::any_tile(rules));
+        for (x, y, z) in PosIter3D::new(map){
+            let val = map.get(x, y, z);
+            if val != T::EMPTY{
+                solution.set(x, y, z, DynBitSet::new_from_vec(rules.len(), vec![val]));
+                if !map.is_border(x, y, z){
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
+            }
+        }
+        (solution, to_propagate)
+    }
+
+    //every tile which some rule allows
+    pub(crate) fn any_tile(rules: &[DirectionMapping<DynBitSet>]) -> DynBitSet
+    {
+        let mut any = DynBitSet::new_empty(rules.len());
+        for dir_rules in rules
+        {
+            for direction in 0..6
+            {
+                any.union_with(dir_rules.get(direction));
+            }
+        }
+        any
+    }
+
+    pub(crate) fn propagate<T: Tile>(solution: & mut Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>], to_propagate: & mut VecDeque<(usize,usize,usize)>, step: usize) -> Result<(), Contradiction<T>>
+    {
+        QueuePropDynSet::propagate_metered(solution, rules, to_propagate, step, &mut Unmetered{}).map_err(SolveError::into_contradiction)
+    }
+
+    fn propagate_metered<T: Tile, M: Metering>(solution: & mut Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>], to_propagate: & mut VecDeque<(usize,usize,usize)>, step: usize, meter: & mut M) -> Result<(), SolveError<Grid3D, T>>
+    {
+        while let Some((x, y, z)) = to_propagate.pop_front() {
+            if let Err(reason) = meter.propagation()
+            {
+                return Err(SolveError::Stopped{reason, step, partial: QueuePropDynSet::format_partial(solution)});
+            }
+            if solution.get_ref(x, y, z).len() > 1
+            {
+                let updated = QueuePropDynSet::legal_tiles(x, y, z, solution, rules);
+                if &updated != solution.get_ref(x, y, z) {
+                    let wiped = updated.is_empty();
+                    solution.set(x, y, z, updated);
+                    if wiped
+                    {
+                        let neighbor = utils::wiping_neighbor(QueuePropDynSet::neighbor_constraints(x, y, z, solution, rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, step, QueuePropDynSet::format_solution(solution)).into());
+                    }
+                    //propagation changed state, need to continue propagation
+                    to_propagate.extend(neighbors((x, y, z)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //collapses every undecided cell of the wave
+    pub(crate) fn collapse<T: Tile>(solution: & mut Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>], weights: &[f64], selection: CellSelection, rng: &mut dyn RngCore) -> Result<(), Contradiction<T>>
+    {
+        QueuePropDynSet::collapse_metered(solution, rules, weights, selection, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)
+    }
+
+    fn collapse_metered<T: Tile, M: Metering>(solution: & mut Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>], weights: &[f64], selection: CellSelection, meter: & mut M, rng: &mut dyn RngCore) -> Result<(), SolveError<Grid3D, T>>
+    {
+        let mut step = 0;
+        loop {
+            let minimal = match selection {
+                CellSelection::MinimumRemaining => QueuePropDynSet::find_minimal(solution),
+                CellSelection::Entropy => utils::find_minimal_entropy(solution, DynBitSet::items, weights, rng)
+            };
+            match minimal {
+                Some((x, y, z)) => {
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: QueuePropDynSet::format_partial(solution)});
+                    }
+                    //minimal found setting it randomly according to weights
+                    let tile = utils::get_weighted_random_with(rng, solution.get_ref(x, y, z).items().into_iter().map(T::from_index), weights);
+                    solution.set(x, y, z, DynBitSet::new_from_vec(rules.len(), vec![tile]));
+                    step += 1;
+                    let mut to_propagate = VecDeque::from(neighbors((x, y, z)));
+                    QueuePropDynSet::propagate_metered(solution, rules, &mut to_propagate, step, meter)?;
+                },
+                None => {
+                    //nothing left to be collapsed
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn find_minimal(solution: &Vec3D<DynBitSet>) -> Option<(usize, usize, usize)>
+    {
+        PosIter3D::new(solution)
+            .map(|(x, y, z)| (x, y, z, solution.get_ref(x, y, z).len()))
+            .filter(|&(_, _, _, len)| len > 1)
+            .min_by_key(|&(_, _, _, len)| len)
+            .map(|(x, y, z, _)| (x, y, z))
+    }
+
+    pub(crate) fn legal_tiles(x: usize, y: usize, z: usize, map: &Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>]) -> DynBitSet
+    {
+        let mut legal = DynBitSet::new_any(rules.len());
+        for constraint in QueuePropDynSet::neighbor_constraints(x, y, z, map, rules)
+        {
+            legal.intersect_with(&constraint);
+        }
+        legal
+    }
+
+    fn neighbor_constraints(x: usize, y: usize, z: usize, map: &Vec3D<DynBitSet>, rules: &[DirectionMapping<DynBitSet>]) -> Vec<DynBitSet>
+    {
+        neighbors((x, y, z)).iter().zip(CONSTRAINING_DIRECTION).map(|(&(nx, ny, nz), direction)| {
+            let mut allowed = DynBitSet::new_empty(rules.len());
+            for tile in map.get_ref(nx, ny, nz).items()
+            {
+                allowed.union_with(rules[tile].get(direction));
+            }
+            allowed
+        }).collect()
+    }
+
+    //cells without legal tiles hold EMPTY, undecided cells hold their first candidate
+    pub(crate) fn format_solution<T: Tile>(solution: &Vec3D<DynBitSet>) -> Vec3D<T>
+    {
+        let mut ret = Vec3D::new(solution.x_size, solution.y_size, solution.z_size, T::EMPTY);
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            ret.set(x, y, z, solution.get_ref(x, y, z).items().first().map_or(T::EMPTY, |&tile| T::from_index(tile)));
+        }
+        ret
+    }
+
+    //decided cells hold their tile and the rest is EMPTY
+    fn format_partial<T: Tile>(solution: &Vec3D<DynBitSet>) -> Vec3D<T>
+    {
+        let mut ret = QueuePropDynSet::format_solution(solution);
+        for (x, y, z) in PosIter3D::new(solution)
+        {
+            if solution.get_ref(x, y, z).len() > 1
+            {
+                ret.set(x, y, z, T::EMPTY);
+            }
+        }
+        ret
+    }
+}
+
+impl WFC for QueuePropDynSet{
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        QueuePropDynSet::solve(map, rules, &vec![1.0; rules.len()], CellSelection::MinimumRemaining, meter, rng)
+    }
+}
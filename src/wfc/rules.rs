@@ -1,20 +1,20 @@
 use std::collections::HashSet;
 
-use super::direction_mapping::DirectionMapping;
+use super::{direction_mapping::DirectionMapping, direction_mapping2d::DirectionMapping2D};
 
-const UP: u8    = 0b1000_0000;
-const DOWN: u8  = 0b0100_0000;
-const LEFT: u8  = 0b0010_0000;
-const RIGHT: u8 = 0b0001_0000;
-const FRONT: u8 = 0b0000_1000;
-const BACK: u8  = 0b0000_0100;
-const RED: u8   = 0b0000_0000;
-const GREEN: u8 = 0b0000_0001;
-const BLUE: u8  = 0b0000_0010;
+pub const UP: u8    = 0b1000_0000;
+pub const DOWN: u8  = 0b0100_0000;
+pub const LEFT: u8  = 0b0010_0000;
+pub const RIGHT: u8 = 0b0001_0000;
+pub const FRONT: u8 = 0b0000_1000;
+pub const BACK: u8  = 0b0000_0100;
+pub const RED: u8   = 0b0000_0000;
+pub const GREEN: u8 = 0b0000_0001;
+pub const BLUE: u8  = 0b0000_0010;
 const SPECIAL: u8 = 0b00000011;
 pub const EMPTY: u8  = 0b00000011;
 pub const BORDER: u8 = 0b00000111;
-const TYPE_MASK: u8 = 0b0000_0011;
+pub const TYPE_MASK: u8 = 0b0000_0011;
 
 fn is_special_type(a: u8) -> bool
 {
@@ -54,20 +54,20 @@ fn get_pipe_dir_rules(i: u8, dir: u8, pipe_type: u8) -> HashSet<u8>
     if i & dir != 0
     {
         HashSet::from_iter(
-            (0..u8::MAX).filter(|&a| is_pipe_with_direction(a, get_oposite_dir(dir)) && a & TYPE_MASK == pipe_type || a == BORDER)
+            (0..=u8::MAX).filter(|&a| is_pipe_with_direction(a, get_oposite_dir(dir)) && a & TYPE_MASK == pipe_type || a == BORDER)
         )
     }
     else
     {
         HashSet::from_iter(
-            (0..u8::MAX).filter(|&a| is_pipe_without_direction(a, get_oposite_dir(dir)) || a == BORDER)
+            (0..=u8::MAX).filter(|&a| is_pipe_without_direction(a, get_oposite_dir(dir)) || a == BORDER)
         )
     }
 }
 
 pub fn get_any_tile() -> HashSet<u8>
 {
-    (0..u8::MAX).filter(|&a| is_pipe(a) || a == BORDER).collect()
+    (0..=u8::MAX).filter(|&a| is_pipe(a) || a == BORDER).collect()
 }
 
 fn get_pipe_rules(i: u8) -> DirectionMapping<HashSet<u8>>
@@ -104,7 +104,29 @@ fn get_pipe_rules(i: u8) -> DirectionMapping<HashSet<u8>>
     )
 }
 
+pub fn get_uniform_weights() -> Vec<f64>
+{
+    vec![1.0; u8::MAX as usize + 1]
+}
+
 pub fn get_pipes_rules() -> Vec<DirectionMapping<HashSet<u8>>>
 {
-    (0..u8::MAX).map(|a| get_pipe_rules(a)).collect()
+    (0..=u8::MAX).map(|a| get_pipe_rules(a)).collect()
+}
+
+//pipes lying in the xy plane, tiles with front or back connections are never allowed
+pub fn get_pipes_rules_2d() -> Vec<DirectionMapping2D<HashSet<u8>>>
+{
+    let is_flat = |a: u8| !is_pipe(a) || a & (FRONT | BACK) == 0;
+    get_pipes_rules().iter().enumerate().map(|(a, dir_rules)| {
+        let flat = |set: &HashSet<u8>| set.iter().cloned().filter(|&b| is_flat(b)).collect::<HashSet<u8>>();
+        if is_flat(a as u8)
+        {
+            DirectionMapping2D::new(flat(dir_rules.up()), flat(dir_rules.down()), flat(dir_rules.right()), flat(dir_rules.left()))
+        }
+        else
+        {
+            DirectionMapping2D::new(HashSet::new(), HashSet::new(), HashSet::new(), HashSet::new())
+        }
+    }).collect()
 }
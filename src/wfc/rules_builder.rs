@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use super::{direction_mapping::{DirectionMapping, OFFSETS}, rules::{EMPTY, BORDER}, vec3d::{Vec3D, PosIter3D}};
+
+//learns which tiles may neighbor each other from hand made examples
+//EMPTY cells of an example are unknown and do not add any rule
+pub struct RulesBuilder{
+    rules: Vec<DirectionMapping<HashSet<u8>>>,
+    frequencies: Vec<f64>
+}
+
+impl RulesBuilder
+{
+    pub fn new() -> RulesBuilder
+    {
+        RulesBuilder{
+            rules: (0..=u8::MAX).map(|_| DirectionMapping::from_fn(|_| HashSet::new())).collect(),
+            frequencies: vec![0.0; u8::MAX as usize + 1]
+        }
+    }
+
+    pub fn from_example(example: &Vec3D<u8>) -> RulesBuilder
+    {
+        let mut builder = RulesBuilder::new();
+        builder.add_example(example);
+        builder
+    }
+
+    pub fn add_example(&mut self, example: &Vec3D<u8>) -> &mut RulesBuilder
+    {
+        for (x, y, z) in PosIter3D::new(example)
+        {
+            let tile = example.get(x, y, z);
+            if !RulesBuilder::is_known(tile)
+            {
+                continue;
+            }
+            self.frequencies[tile as usize] += 1.0;
+            for (direction, &(dx, dy, dz)) in OFFSETS.iter().enumerate()
+            {
+                let (nx, ny, nz) = (x as isize + dx, y as isize + dy, z as isize + dz);
+                if nx < 0 || ny < 0 || nz < 0 || nx as usize >= example.x_size || ny as usize >= example.y_size || nz as usize >= example.z_size
+                {
+                    continue;
+                }
+                let neighbor = example.get(nx as usize, ny as usize, nz as usize);
+                if RulesBuilder::is_known(neighbor)
+                {
+                    self.rules[tile as usize].get_mut(direction).insert(neighbor);
+                }
+            }
+        }
+        self
+    }
+
+    //rules in the same form as rules::get_pipes_rules
+    //if examples have no BORDER it accepts every observed tile, like the border of pipe rules
+    pub fn rules(&self) -> Vec<DirectionMapping<HashSet<u8>>>
+    {
+        let mut rules = self.rules.clone();
+        if self.frequencies[BORDER as usize] == 0.0
+        {
+            let observed: HashSet<u8> = (0..=u8::MAX).filter(|&tile| self.frequencies[tile as usize] > 0.0).collect();
+            rules[BORDER as usize] = DirectionMapping::from_fn(|_| observed.clone());
+        }
+        rules
+    }
+
+    //number of times each tile was observed, usable as weights
+    pub fn frequencies(&self) -> Vec<f64>
+    {
+        self.frequencies.clone()
+    }
+
+    fn is_known(tile: u8) -> bool
+    {
+        tile != EMPTY
+    }
+}
+
+impl Default for RulesBuilder
+{
+    fn default() -> Self {
+        RulesBuilder::new()
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashSet, fmt};
+
+use super::{direction_mapping::{DirectionMapping, NAMES, opposite}, rules::{EMPTY, BORDER}};
+
+//directions are indexed in the order of direction_mapping::OFFSETS
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RuleIssue{
+    //tile allows other in direction, but other does not allow tile in the opposite direction
+    Asymmetric{tile: u8, direction: usize, other: u8},
+    //tile has no rules in direction, so it can not have any neighbor there
+    EmptyDirection{tile: u8, direction: usize},
+    //rule references a tile which has no rules of its own or does not fit into the rules
+    UnknownTile{tile: u8, direction: usize, other: u8},
+    //no interior cell can ever hold tile, whatever its neighbors are
+    Unplaceable{tile: u8}
+}
+
+impl fmt::Display for RuleIssue
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RuleIssue::Asymmetric{tile, direction, other} =>
+                write!(f, "tile {} allows {} {} but {} does not allow {} {}", tile, other, NAMES[direction], other, tile, NAMES[opposite(direction)]),
+            RuleIssue::EmptyDirection{tile, direction} =>
+                write!(f, "tile {} allows nothing {}", tile, NAMES[direction]),
+            RuleIssue::UnknownTile{tile, direction, other} =>
+                write!(f, "tile {} allows unknown tile {} {}", tile, other, NAMES[direction]),
+            RuleIssue::Unplaceable{tile} =>
+                write!(f, "tile {} can never be placed", tile)
+        }
+    }
+}
+
+//tiles of the rule set are the ones with any rule, EMPTY is never a tile
+pub fn tiles(rules: &[DirectionMapping<HashSet<u8>>]) -> Vec<u8>
+{
+    (0..rules.len())
+        .filter(|&tile| tile != EMPTY as usize && (0..6).any(|direction| !rules[tile].get(direction).is_empty()))
+        .map(|tile| tile as u8)
+        .collect()
+}
+
+//every issue found in rules, empty if rules are consistent
+pub fn check(rules: &[DirectionMapping<HashSet<u8>>]) -> Vec<RuleIssue>
+{
+    let tiles = tiles(rules);
+    let known: HashSet<u8> = tiles.iter().cloned().collect();
+    let mut issues = Vec::new();
+    for &tile in &tiles
+    {
+        for direction in 0..6
+        {
+            let allowed = rules[tile as usize].get(direction);
+            if allowed.is_empty()
+            {
+                issues.push(RuleIssue::EmptyDirection{tile, direction});
+            }
+            let mut sorted: Vec<u8> = allowed.iter().cloned().collect();
+            sorted.sort();
+            for other in sorted
+            {
+                if !known.contains(&other)
+                {
+                    issues.push(RuleIssue::UnknownTile{tile, direction, other});
+                }
+                else if !rules[other as usize].get(opposite(direction)).contains(&tile)
+                {
+                    issues.push(RuleIssue::Asymmetric{tile, direction, other});
+                }
+            }
+        }
+    }
+    issues.extend(unplaceable(rules, &tiles).into_iter().map(|tile| RuleIssue::Unplaceable{tile}));
+    issues
+}
+
+//removes tiles which lack a supporting neighbor in some direction until nothing changes
+//BORDER is only next to the edge of a grid, so it does not support interior tiles
+fn unplaceable(rules: &[DirectionMapping<HashSet<u8>>], tiles: &[u8]) -> Vec<u8>
+{
+    let mut possible: HashSet<u8> = tiles.iter().cloned().filter(|&tile| tile != BORDER).collect();
+    loop {
+        let removed: Vec<u8> = possible.iter().cloned().filter(|&tile| {
+            (0..6).any(|direction| !possible.iter().any(|&other| rules[other as usize].get(opposite(direction)).contains(&tile)))
+        }).collect();
+        if removed.is_empty()
+        {
+            break;
+        }
+        for tile in removed
+        {
+            possible.remove(&tile);
+        }
+    }
+    tiles.iter().cloned().filter(|&tile| tile != BORDER && !possible.contains(&tile)).collect()
+}
@@ -1,12 +1,15 @@
 use std::collections::HashSet;
 
-use super::{direction_mapping::DirectionMapping, rules::EMPTY, traits::WFC, vec3d::Vec3D, utils::get_random};
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules::EMPTY, traits::{WFC, Contradiction}, budget::{Metering, SolveError}, vec3d::{Vec3D, neighbors}, utils::{self, get_random_with}};
 
 pub struct Simple{}
 impl WFC for Simple{
-    fn solve(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
     {
         let mut solution = map.clone();
+        let mut step = 0;
         loop {
             let mut collapsed = false;
             for x in 1..map.x_size-1{
@@ -14,6 +17,11 @@ impl WFC for Simple{
                     for z in 1.. map.z_size-1{
                         if solution.get(x, y, z) == EMPTY
                         {
+                            //every sweep over undecided cells is the propagation of this solver
+                            if let Err(reason) = meter.propagation()
+                            {
+                                return Err(SolveError::Stopped{reason, step, partial: solution});
+                            }
                             let legal = Simple::legal_tiles(&solution, x, y, z, rules);
                             if Simple::is_collapsible(&legal){
                                 solution.set(x, y, z, *legal.iter().next().unwrap());
@@ -31,6 +39,10 @@ impl WFC for Simple{
                     for y in 1..map.y_size-1{
                         for z in 1.. map.z_size-1{
                             let number_of_legal_tiles = Simple::legal_tiles(&solution, x, y, z, rules).len();
+                            if solution.get(x, y, z) == EMPTY && number_of_legal_tiles == 0{
+                                let neighbor = utils::wiping_neighbor(Simple::neighbor_constraints(&solution, x, y, z, rules).into_iter().cloned().zip(neighbors((x, y, z))), (x, y, z));
+                                return Err(Contradiction::new((x, y, z), neighbor, step, solution).into());
+                            }
                             if solution.get(x, y, z) == EMPTY && number_of_legal_tiles > 0 && number_of_legal_tiles < min{
                                 min = number_of_legal_tiles;
                                 min_position = (x,y,z);
@@ -39,12 +51,20 @@ impl WFC for Simple{
                     }
                 }
                 if min != usize::MAX{ //if found min position left to fill
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: solution});
+                    }
+                    //hash set order differs between runs, sorting keeps seeded runs reproducible
+                    let mut legal = Vec::from_iter(Simple::legal_tiles(&solution, min_position.0, min_position.1, min_position.2, rules));
+                    legal.sort();
                     solution.set(
                         min_position.0, min_position.1, min_position.2,
-                        get_random(Simple::legal_tiles(&solution, min_position.0, min_position.1, min_position.2, rules)));
+                        get_random_with(rng, legal));
+                    step += 1;
                 }
                 else{
-                    return solution;
+                    return Ok(solution);
                 }
             }
         }
@@ -55,13 +75,7 @@ impl Simple
 {
     fn legal_tiles(map: &Vec3D<u8>, x: usize, y: usize, z: usize, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> HashSet<u8>
     {
-        let possible_tiles = vec![
-            rules[map.get(x, y + 1, z) as usize].down(),
-            rules[map.get(x, y - 1, z) as usize].up(),
-            rules[map.get(x - 1, y, z) as usize].left(),
-            rules[map.get(x + 1, y, z) as usize].right(),
-            rules[map.get(x, y, z - 1) as usize].front(),
-            rules[map.get(x, y, z + 1) as usize].back()];
+        let possible_tiles = Simple::neighbor_constraints(map, x, y, z, rules);
         possible_tiles
             .iter()
             .skip(1)
@@ -69,6 +83,16 @@ impl Simple
                 acc.intersection(val).cloned().collect()
             })
     }
+    fn neighbor_constraints<'a>(map: &Vec3D<u8>, x: usize, y: usize, z: usize, rules: &'a Vec<DirectionMapping<HashSet<u8>>>) -> Vec<&'a HashSet<u8>>
+    {
+        vec![
+            rules[map.get(x, y + 1, z) as usize].down(),
+            rules[map.get(x, y - 1, z) as usize].up(),
+            rules[map.get(x - 1, y, z) as usize].left(),
+            rules[map.get(x + 1, y, z) as usize].right(),
+            rules[map.get(x, y, z - 1) as usize].front(),
+            rules[map.get(x, y, z + 1) as usize].back()]
+    }
     fn is_collapsible(legal_tiles: &HashSet<u8>) -> bool{
         legal_tiles.len() == 1
     }
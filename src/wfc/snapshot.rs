@@ -0,0 +1,289 @@
+use std::{collections::VecDeque, fmt};
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use super::{direction_mapping::DirectionMapping, bits256_set::Bits256Set, vec3d::Vec3D};
+
+const MAGIC: &[u8; 4] = b"WFCS";
+const VERSION: u8 = 1;
+
+//rng giving the same numbers as StdRng, with a position which can be stored and restored directly
+pub struct ResumableRng{
+    seed: u64,
+    rng: ChaCha12Rng
+}
+
+impl ResumableRng
+{
+    pub fn new(seed: u64) -> ResumableRng
+    {
+        ResumableRng{seed, rng: ChaCha12Rng::seed_from_u64(seed)}
+    }
+
+    //continues after word_pos 32 bit words of the stream of seed
+    pub fn restore(seed: u64, word_pos: u128) -> ResumableRng
+    {
+        let mut rng = ResumableRng::new(seed);
+        rng.rng.set_word_pos(word_pos);
+        rng
+    }
+
+    pub fn seed(&self) -> u64
+    {
+        self.seed
+    }
+
+    pub fn word_pos(&self) -> u128
+    {
+        self.rng.get_word_pos()
+    }
+}
+
+impl RngCore for ResumableRng
+{
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError{
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Invalid(&'static str)
+}
+
+impl fmt::Display for SnapshotError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a solver snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "unsupported snapshot version {}", version),
+            SnapshotError::Truncated => write!(f, "snapshot ends too early"),
+            SnapshotError::Invalid(what) => write!(f, "invalid snapshot: {}", what)
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+//little endian encoding of solver state
+pub(crate) struct Writer{
+    bytes: Vec<u8>
+}
+
+impl Writer
+{
+    pub(crate) fn new() -> Writer
+    {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        Writer{bytes}
+    }
+
+    pub(crate) fn u64(&mut self, value: u64)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn bool(&mut self, value: bool)
+    {
+        self.bytes.push(value as u8);
+    }
+
+    pub(crate) fn set(&mut self, set: &Bits256Set)
+    {
+        let (first, second) = set.parts();
+        self.bytes.extend_from_slice(&first.to_le_bytes());
+        self.bytes.extend_from_slice(&second.to_le_bytes());
+    }
+
+    pub(crate) fn rules(&mut self, rules: &Vec<DirectionMapping<Bits256Set>>)
+    {
+        self.u64(rules.len() as u64);
+        for dir_rules in rules
+        {
+            for direction in 0..6
+            {
+                self.set(dir_rules.get(direction));
+            }
+        }
+    }
+
+    pub(crate) fn wave(&mut self, wave: &Vec3D<Bits256Set>)
+    {
+        self.u64(wave.x_size as u64);
+        self.u64(wave.y_size as u64);
+        self.u64(wave.z_size as u64);
+        for z in 0..wave.z_size
+        {
+            for y in 0..wave.y_size
+            {
+                for x in 0..wave.x_size
+                {
+                    self.set(wave.get_ref(x, y, z));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn positions(&mut self, positions: &VecDeque<(usize, usize, usize)>)
+    {
+        self.u64(positions.len() as u64);
+        for &(x, y, z) in positions
+        {
+            self.u64(x as u64);
+            self.u64(y as u64);
+            self.u64(z as u64);
+        }
+    }
+
+    pub(crate) fn rng(&mut self, rng: &ResumableRng)
+    {
+        self.u64(rng.seed());
+        self.u64(rng.word_pos() as u64);
+        self.u64((rng.word_pos() >> 64) as u64);
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8>
+    {
+        self.bytes
+    }
+}
+
+pub(crate) struct Reader<'a>{
+    bytes: &'a [u8]
+}
+
+impl<'a> Reader<'a>
+{
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Reader<'a>, SnapshotError>
+    {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC
+        {
+            return Err(SnapshotError::BadMagic);
+        }
+        let mut reader = Reader{bytes: &bytes[MAGIC.len()..]};
+        let version = reader.take(1)?[0];
+        if version != VERSION
+        {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError>
+    {
+        if self.bytes.len() < len
+        {
+            return Err(SnapshotError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, SnapshotError>
+    {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    //length which has to be backed by at least item_size bytes per item
+    fn len(&mut self, item_size: usize) -> Result<usize, SnapshotError>
+    {
+        let len = self.u64()? as usize;
+        if len.checked_mul(item_size).map_or(true, |size| size > self.bytes.len())
+        {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, SnapshotError>
+    {
+        match self.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Invalid("flag is neither 0 nor 1"))
+        }
+    }
+
+    pub(crate) fn set(&mut self) -> Result<Bits256Set, SnapshotError>
+    {
+        let first = u128::from_le_bytes(self.take(16)?.try_into().unwrap());
+        let second = u128::from_le_bytes(self.take(16)?.try_into().unwrap());
+        Ok(Bits256Set::new(first, second))
+    }
+
+    pub(crate) fn rules(&mut self) -> Result<Vec<DirectionMapping<Bits256Set>>, SnapshotError>
+    {
+        let len = self.len(6 * 32)?;
+        (0..len).map(|_| Ok(DirectionMapping::new(self.set()?, self.set()?, self.set()?, self.set()?, self.set()?, self.set()?))).collect()
+    }
+
+    pub(crate) fn wave(&mut self) -> Result<Vec3D<Bits256Set>, SnapshotError>
+    {
+        let (x_size, y_size, z_size) = (self.u64()? as usize, self.u64()? as usize, self.u64()? as usize);
+        let cells = x_size.checked_mul(y_size).and_then(|cells| cells.checked_mul(z_size));
+        if cells.and_then(|cells| cells.checked_mul(32)).map_or(true, |size| size > self.bytes.len())
+        {
+            return Err(SnapshotError::Truncated);
+        }
+        let mut wave = Vec3D::new(x_size, y_size, z_size, Bits256Set::new_empty());
+        for z in 0..z_size
+        {
+            for y in 0..y_size
+            {
+                for x in 0..x_size
+                {
+                    wave.set(x, y, z, self.set()?);
+                }
+            }
+        }
+        Ok(wave)
+    }
+
+    //positions have to lie inside of wave
+    pub(crate) fn positions(&mut self, wave: &Vec3D<Bits256Set>) -> Result<VecDeque<(usize, usize, usize)>, SnapshotError>
+    {
+        let len = self.len(24)?;
+        (0..len).map(|_| {
+            let (x, y, z) = (self.u64()? as usize, self.u64()? as usize, self.u64()? as usize);
+            if x >= wave.x_size || y >= wave.y_size || z >= wave.z_size
+            {
+                return Err(SnapshotError::Invalid("position outside of wave"));
+            }
+            Ok((x, y, z))
+        }).collect()
+    }
+
+    pub(crate) fn rng(&mut self) -> Result<ResumableRng, SnapshotError>
+    {
+        let seed = self.u64()?;
+        let word_pos = self.u64()? as u128 | (self.u64()? as u128) << 64;
+        Ok(ResumableRng::restore(seed, word_pos))
+    }
+
+    pub(crate) fn finish(self) -> Result<(), SnapshotError>
+    {
+        if !self.bytes.is_empty()
+        {
+            return Err(SnapshotError::Invalid("unexpected bytes after snapshot"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, utils, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, PosIter3D, neighbors}, traits::Contradiction, snapshot::{ResumableRng, SnapshotError, Writer, Reader}};
+
+#[derive(Clone, Debug)]
+pub enum StepEvent{
+    //cell was collapsed to a single tile
+    Collapsed{position: (usize, usize, usize), tile: u8},
+    //cells whose candidates were narrowed, in the order of propagation
+    Propagated{changed_cells: Vec<(usize, usize, usize)>},
+    Contradiction(Contradiction),
+    Done
+}
+
+pub struct Stepper<R: RngCore>
+{
+    rules: Vec<DirectionMapping<Bits256Set>>,
+    wave: Vec3D<Bits256Set>,
+    to_propagate: VecDeque<(usize, usize, usize)>,
+    rng: R,
+    step: usize,
+    finished: bool
+}
+
+impl<R: RngCore> Stepper<R>
+{
+    pub fn new(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], rng: R) -> Stepper<R>
+    {
+        let rules = QueuePropBitArraySet::prepare_rules(rules);
+        let (wave, to_propagate) = QueuePropBitArraySet::prepare_wave(map, &rules);
+        Stepper{
+            rules,
+            wave,
+            to_propagate,
+            rng,
+            step: 0,
+            finished: false
+        }
+    }
+
+    pub fn wave(&self) -> &Vec3D<Bits256Set>
+    {
+        &self.wave
+    }
+
+    //number of collapses made so far
+    pub fn steps(&self) -> usize
+    {
+        self.step
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.finished
+    }
+
+    //current state of the wave, cells which are not collapsed yet hold their first candidate
+    pub fn solution(&self) -> Vec3D<u8>
+    {
+        QueuePropBitArraySet::format_solution(&self.wave)
+    }
+
+    //pending propagation is finished before next cell is collapsed
+    pub fn step(&mut self) -> StepEvent
+    {
+        if self.finished
+        {
+            return StepEvent::Done;
+        }
+        if !self.to_propagate.is_empty()
+        {
+            return match self.propagate() {
+                Ok(changed_cells) => StepEvent::Propagated{changed_cells},
+                Err(contradiction) => {
+                    self.finished = true;
+                    StepEvent::Contradiction(contradiction)
+                }
+            };
+        }
+        match QueuePropBitArraySet::find_minimal(&self.wave) {
+            Some(((x, y, z), current)) => {
+                let tile = utils::get_random_with(&mut self.rng, current.items());
+                self.wave.set(x, y, z, Bits256Set::new_from_vec(vec![tile]));
+                self.step += 1;
+                self.to_propagate.extend(neighbors((x, y, z)));
+                StepEvent::Collapsed{position: (x, y, z), tile}
+            },
+            None => {
+                self.finished = true;
+                StepEvent::Done
+            }
+        }
+    }
+
+    fn propagate(&mut self) -> Result<Vec<(usize, usize, usize)>, Contradiction>
+    {
+        let mut changed_cells = Vec::new();
+        while let Some((x, y, z)) = self.to_propagate.pop_front() {
+            let current = self.wave.get(x, y, z);
+            if current.len() > 1
+            {
+                let updated = QueuePropBitArraySet::legal_tiles((x, y, z), &self.wave, &self.rules);
+                if updated != current {
+                    self.wave.set(x, y, z, updated);
+                    changed_cells.push((x, y, z));
+                    if updated.is_empty()
+                    {
+                        self.to_propagate.clear();
+                        let neighbor = utils::wiping_neighbor(QueuePropBitArraySet::neighbor_constraints((x, y, z), &self.wave, &self.rules).into_iter().zip(neighbors((x, y, z))), (x, y, z));
+                        return Err(Contradiction::new((x, y, z), neighbor, self.step, self.solution()));
+                    }
+                    //propagation changed state, need to continue propagation
+                    self.to_propagate.extend(neighbors((x, y, z)));
+                }
+            }
+        }
+        Ok(changed_cells)
+    }
+}
+
+impl Stepper<ResumableRng>
+{
+    //whole state of the solve, including rules and position of the rng, only steppers over ResumableRng can be stored
+    //stepper never backtracks so there is no decision history to store, snapshots of BackTracking solves are out of scope
+    pub fn snapshot(&self) -> Vec<u8>
+    {
+        let mut writer = Writer::new();
+        writer.u64(self.step as u64);
+        writer.bool(self.finished);
+        writer.rng(&self.rng);
+        writer.rules(&self.rules);
+        writer.wave(&self.wave);
+        writer.positions(&self.to_propagate);
+        writer.finish()
+    }
+
+    //continues exactly where snapshot was taken, with the same choices as without stopping
+    pub fn restore(bytes: &[u8]) -> Result<Stepper<ResumableRng>, SnapshotError>
+    {
+        let mut reader = Reader::new(bytes)?;
+        let step = reader.u64()? as usize;
+        let finished = reader.bool()?;
+        let rng = reader.rng()?;
+        let rules = reader.rules()?;
+        let wave = reader.wave()?;
+        let to_propagate = reader.positions(&wave)?;
+        reader.finish()?;
+        if PosIter3D::new(&wave).any(|(x, y, z)| wave.get(x, y, z).items().iter().any(|&tile| tile as usize >= rules.len()))
+        {
+            return Err(SnapshotError::Invalid("tile without rules in wave"));
+        }
+        if rules.iter().any(|dir_rules| (0..6).any(|direction| dir_rules.get(direction).items().iter().any(|&tile| tile as usize >= rules.len())))
+        {
+            return Err(SnapshotError::Invalid("tile without rules in rules"));
+        }
+        //neighbors of border cells are outside of the wave, so they are never collapsed or propagated
+        if PosIter3D::new(&wave).any(|(x, y, z)| wave.is_border(x, y, z) && wave.get(x, y, z).len() > 1)
+        {
+            return Err(SnapshotError::Invalid("border cell with several candidates"));
+        }
+        Ok(Stepper{rules, wave, to_propagate, rng, step, finished})
+    }
+}
+
+impl<R: RngCore> Iterator for Stepper<R>
+{
+    type Item = StepEvent;
+
+    //yields events until the final Done or Contradiction
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished
+        {
+            None
+        }
+        else
+        {
+            Some(self.step())
+        }
+    }
+}
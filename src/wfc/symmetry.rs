@@ -0,0 +1,69 @@
+use super::{direction_mapping::{DirectionMapping, OFFSETS}, vec3d::{Vec3D, PosIter3D}};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Symmetry{
+    //only the original orientation
+    None,
+    //rotations around Y axis and their mirror images
+    AroundY,
+    //all 48 rotations and reflections of a cube
+    Full
+}
+
+//axis permutation and mirrored axes, target axis i is taken from source axis permutation[i]
+pub type Transform = ([usize; 3], [bool; 3]);
+
+pub const IDENTITY: Transform = ([0, 1, 2], [false; 3]);
+
+pub fn transforms(symmetry: Symmetry) -> Vec<Transform>
+{
+    let flips = |axes: &[usize]| -> Vec<[bool; 3]> {
+        (0..1usize << axes.len()).map(|mask| {
+            let mut flip = [false; 3];
+            for (bit, &axis) in axes.iter().enumerate()
+            {
+                flip[axis] = mask & (1 << bit) != 0;
+            }
+            flip
+        }).collect()
+    };
+    let (permutations, flips) = match symmetry {
+        Symmetry::None => (vec![[0, 1, 2]], vec![[false; 3]]),
+        Symmetry::AroundY => (vec![[0, 1, 2], [2, 1, 0]], flips(&[0, 2])),
+        Symmetry::Full => (vec![[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]], flips(&[0, 1, 2]))
+    };
+    permutations.iter().flat_map(|&permutation| flips.iter().map(move |&flip| (permutation, flip))).collect()
+}
+
+//cube of voxels seen after the transformation
+pub fn transform_voxels(pattern: &Vec3D<u8>, (permutation, flip): &Transform) -> Vec3D<u8>
+{
+    let n = pattern.x_size;
+    let mut result = pattern.clone();
+    for (x, y, z) in PosIter3D::new(pattern)
+    {
+        let target = [x, y, z];
+        let mut source = [0; 3];
+        for axis in 0..3
+        {
+            source[permutation[axis]] = if flip[axis] { n - 1 - target[axis] } else { target[axis] };
+        }
+        result.set(x, y, z, pattern.get(source[0], source[1], source[2]));
+    }
+    result
+}
+
+//faces seen after the transformation, each direction is taken from the face it was turned from
+pub fn transform_faces<T: Clone>(faces: &DirectionMapping<T>, (permutation, flip): &Transform) -> DirectionMapping<T>
+{
+    DirectionMapping::from_fn(|offset| {
+        let target = [offset.0, offset.1, offset.2];
+        let mut source = [0; 3];
+        for axis in 0..3
+        {
+            source[permutation[axis]] = if flip[axis] { -target[axis] } else { target[axis] };
+        }
+        let direction = OFFSETS.iter().position(|&o| o == (source[0], source[1], source[2])).unwrap();
+        faces.get(direction).clone()
+    })
+}
@@ -0,0 +1,38 @@
+use std::{fmt::Debug, hash::Hash};
+
+use super::rules;
+
+//index type of tiles, wider types allow rule sets with more than 255 tiles
+//EMPTY and BORDER keep the values from rules for every width
+pub trait Tile: Copy + Eq + Ord + Hash + Debug
+{
+    const EMPTY: Self;
+    const BORDER: Self;
+
+    fn index(self) -> usize;
+    fn from_index(index: usize) -> Self;
+}
+
+macro_rules! impl_tile {
+    ($($t:ty),*) => {
+        $(
+            impl Tile for $t
+            {
+                const EMPTY: Self = rules::EMPTY as $t;
+                const BORDER: Self = rules::BORDER as $t;
+
+                fn index(self) -> usize
+                {
+                    self as usize
+                }
+
+                fn from_index(index: usize) -> Self
+                {
+                    index as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_tile!(u8, u16, u32);
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{direction_mapping::{DirectionMapping, OFFSETS, opposite}, rules::{EMPTY, BORDER}, symmetry::{self, Symmetry, Transform}};
+
+//ids which have special meaning for solvers and are never given to a variant
+const RESERVED: [u8; 2] = [EMPTY, BORDER];
+
+pub(crate) fn free_ids() -> impl Iterator<Item = u8>
+{
+    (0..=u8::MAX).filter(|id| !RESERVED.contains(id))
+}
+
+pub struct TileDefinition{
+    pub name: String,
+    //socket on each face, faces with equal sockets can touch
+    pub connectors: DirectionMapping<String>,
+    pub symmetry: Symmetry,
+    pub weight: f64
+}
+
+//orientation of a declared tile, weight of the tile is split evenly between its variants
+pub struct TileVariant{
+    pub id: u8,
+    pub tile: usize,
+    pub transform: Transform,
+    pub connectors: DirectionMapping<String>,
+    pub weight: f64
+}
+
+//result of Tileset::build, rules and weights are indexed by variant id
+pub struct BuiltTileset{
+    pub rules: Vec<DirectionMapping<HashSet<u8>>>,
+    pub weights: Vec<f64>,
+    pub variants: Vec<TileVariant>,
+    //name of the declared tile of each variant id
+    pub names: HashMap<u8, String>
+}
+
+pub struct Tileset{
+    tiles: Vec<TileDefinition>
+}
+
+impl Tileset
+{
+    pub fn new() -> Tileset
+    {
+        Tileset{tiles: Vec::new()}
+    }
+
+    pub fn add_tile(&mut self, name: &str, connectors: DirectionMapping<String>, symmetry: Symmetry, weight: f64) -> &mut Tileset
+    {
+        self.tiles.push(TileDefinition{name: name.to_string(), connectors, symmetry, weight});
+        self
+    }
+
+    pub fn tiles(&self) -> &Vec<TileDefinition>
+    {
+        &self.tiles
+    }
+
+    //every distinct orientation of every tile, orientations with the same connectors are generated once
+    //returns None if there are more variants than free tile ids
+    pub fn variants(&self) -> Option<Vec<TileVariant>>
+    {
+        let mut ids = free_ids();
+        let mut variants = Vec::<TileVariant>::new();
+        for (index, tile) in self.tiles.iter().enumerate()
+        {
+            let mut oriented = Vec::<(Transform, DirectionMapping<String>)>::new();
+            for transform in symmetry::transforms(tile.symmetry)
+            {
+                let connectors = symmetry::transform_faces(&tile.connectors, &transform);
+                if !oriented.iter().any(|(_, other)| Tileset::same_connectors(other, &connectors))
+                {
+                    oriented.push((transform, connectors));
+                }
+            }
+            let weight = tile.weight / oriented.len() as f64;
+            for (transform, connectors) in oriented
+            {
+                variants.push(TileVariant{id: ids.next()?, tile: index, transform, connectors, weight});
+            }
+        }
+        Some(variants)
+    }
+
+    //BORDER accepts every variant
+    pub fn build(&self) -> Option<BuiltTileset>
+    {
+        let variants = self.variants()?;
+        let mut rules: Vec<DirectionMapping<HashSet<u8>>> = (0..=u8::MAX).map(|_| DirectionMapping::from_fn(|_| HashSet::new())).collect();
+        let mut weights = vec![0.0; u8::MAX as usize + 1];
+        for variant in &variants
+        {
+            weights[variant.id as usize] = variant.weight;
+            for direction in 0..OFFSETS.len()
+            {
+                let opposite = opposite(direction);
+                let allowed = rules[variant.id as usize].get_mut(direction);
+                for other in &variants
+                {
+                    if variant.connectors.get(direction) == other.connectors.get(opposite)
+                    {
+                        allowed.insert(other.id);
+                    }
+                }
+            }
+        }
+        let all: HashSet<u8> = variants.iter().map(|variant| variant.id).collect();
+        rules[BORDER as usize] = DirectionMapping::from_fn(|_| all.clone());
+        let names = variants.iter().map(|variant| (variant.id, self.tiles[variant.tile].name.clone())).collect();
+        Some(BuiltTileset{rules, weights, variants, names})
+    }
+
+    fn same_connectors(a: &DirectionMapping<String>, b: &DirectionMapping<String>) -> bool
+    {
+        (0..OFFSETS.len()).all(|direction| a.get(direction) == b.get(direction))
+    }
+}
+
+impl Default for Tileset
+{
+    fn default() -> Self {
+        Tileset::new()
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashSet, fmt, fs, path::Path};
+
+use serde::Deserialize;
+
+use super::{direction_mapping::DirectionMapping, symmetry::Symmetry, tileset::{Tileset, BuiltTileset}};
+
+//tileset written as toml, every tile lists the socket on each of its faces
+//
+//[[tile]]
+//name = "corner"
+//symmetry = "around_y"
+//weight = 0.5
+//sockets = { up = "air", down = "floor", right = "pipe", left = "wall", front = "pipe", back = "wall" }
+//
+//symmetry is one of "none", "around_y" and "full" and defaults to "none", weight defaults to 1
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TilesetFile{
+    #[serde(default)]
+    tile: Vec<TileEntry>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TileEntry{
+    name: String,
+    sockets: Sockets,
+    symmetry: Option<String>,
+    weight: Option<f64>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Sockets{
+    up: String,
+    down: String,
+    right: String,
+    left: String,
+    front: String,
+    back: String
+}
+
+#[derive(Debug)]
+pub enum FormatError{
+    Io(std::io::Error),
+    Parse(String),
+    DuplicateTile(String),
+    UnknownSymmetry{tile: String, symmetry: String},
+    InvalidWeight{tile: String, weight: f64},
+    TooManyVariants
+}
+
+impl fmt::Display for FormatError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(error) => write!(f, "can not read tileset: {}", error),
+            FormatError::Parse(message) => write!(f, "invalid tileset: {}", message),
+            FormatError::DuplicateTile(tile) => write!(f, "tile {} is declared more than once", tile),
+            FormatError::UnknownSymmetry{tile, symmetry} => write!(f, "tile {} has unknown symmetry {}, expected none, around_y or full", tile, symmetry),
+            FormatError::InvalidWeight{tile, weight} => write!(f, "tile {} has weight {}, expected a positive number", tile, weight),
+            FormatError::TooManyVariants => write!(f, "tileset has more orientations than free tile ids")
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+pub fn parse(text: &str) -> Result<Tileset, FormatError>
+{
+    let file: TilesetFile = toml::from_str(text).map_err(|error| FormatError::Parse(error.to_string()))?;
+    let mut names = HashSet::new();
+    let mut tileset = Tileset::new();
+    for entry in file.tile
+    {
+        if !names.insert(entry.name.clone())
+        {
+            return Err(FormatError::DuplicateTile(entry.name));
+        }
+        let symmetry = match entry.symmetry.as_deref().unwrap_or("none") {
+            "none" => Symmetry::None,
+            "around_y" => Symmetry::AroundY,
+            "full" => Symmetry::Full,
+            other => return Err(FormatError::UnknownSymmetry{tile: entry.name, symmetry: other.to_string()})
+        };
+        let weight = entry.weight.unwrap_or(1.0);
+        if !(weight.is_finite() && weight > 0.0)
+        {
+            return Err(FormatError::InvalidWeight{tile: entry.name, weight});
+        }
+        let Sockets{up, down, right, left, front, back} = entry.sockets;
+        tileset.add_tile(&entry.name, DirectionMapping::new(up, down, right, left, front, back), symmetry, weight);
+    }
+    Ok(tileset)
+}
+
+//rules, weights and tile names ready for the solvers
+pub fn load(text: &str) -> Result<BuiltTileset, FormatError>
+{
+    parse(text)?.build().ok_or(FormatError::TooManyVariants)
+}
+
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<BuiltTileset, FormatError>
+{
+    load(&fs::read_to_string(path).map_err(FormatError::Io)?)
+}
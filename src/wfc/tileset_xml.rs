@@ -0,0 +1,192 @@
+use std::{collections::{HashMap, HashSet}, fmt, fs, path::Path};
+
+use super::{direction_mapping::DirectionMapping, rules::BORDER, tileset::free_ids};
+
+//tilesets of the simple tiled model from the original WaveFunctionCollapse project
+//
+//<set>
+//  <tiles>
+//    <tile name="corner" symmetry="L" weight="0.5"/>
+//  </tiles>
+//  <neighbors>
+//    <neighbor left="corner 1" right="line"/>
+//  </neighbors>
+//  <subsets>
+//    <subset name="small"><tile name="corner"/></subset>
+//  </subsets>
+//</set>
+//
+//tiles lie in the xy plane with y growing downwards like image rows of the original,
+//front and back accept every tile so each z layer is solved on its own
+pub struct XmlTileset{
+    pub rules: Vec<DirectionMapping<HashSet<u8>>>,
+    pub weights: Vec<f64>,
+    //variant names as in the original, tile name followed by orientation index
+    pub names: HashMap<u8, String>
+}
+
+#[derive(Debug)]
+pub enum XmlError{
+    Io(std::io::Error),
+    Parse(String),
+    MissingAttribute{element: String, attribute: String},
+    InvalidAttribute{element: String, attribute: String, value: String},
+    DuplicateTile(String),
+    UnknownTile(String),
+    UnknownSubset(String),
+    TooManyTiles
+}
+
+impl fmt::Display for XmlError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlError::Io(error) => write!(f, "can not read tileset: {}", error),
+            XmlError::Parse(message) => write!(f, "invalid xml: {}", message),
+            XmlError::MissingAttribute{element, attribute} => write!(f, "{} has no {} attribute", element, attribute),
+            XmlError::InvalidAttribute{element, attribute, value} => write!(f, "{} has invalid {} {}", element, attribute, value),
+            XmlError::DuplicateTile(tile) => write!(f, "tile {} is declared more than once", tile),
+            XmlError::UnknownTile(tile) => write!(f, "neighbor refers to undeclared tile {}", tile),
+            XmlError::UnknownSubset(subset) => write!(f, "subset {} is not declared", subset),
+            XmlError::TooManyTiles => write!(f, "tileset has more orientations than free tile ids")
+        }
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+//number of orientations, variant reached by rotating and by reflecting each orientation
+type Symmetry = (usize, fn(usize) -> usize, fn(usize) -> usize);
+
+fn symmetry(letter: &str) -> Option<Symmetry>
+{
+    let symmetry: Symmetry = match letter {
+        "X" => (1, |i| i, |i| i),
+        "I" => (2, |i| 1 - i, |i| i),
+        "\\" => (2, |i| 1 - i, |i| 1 - i),
+        "T" => (4, |i| (i + 1) % 4, |i| if i % 2 == 0 { i } else { 4 - i }),
+        "L" => (4, |i| (i + 1) % 4, |i| if i % 2 == 0 { i + 1 } else { i - 1 }),
+        "F" => (8, |i| if i < 4 { (i + 1) % 4 } else { 4 + (i - 1) % 4 }, |i| if i < 4 { i + 4 } else { i - 4 }),
+        _ => return None
+    };
+    Some(symmetry)
+}
+
+fn attribute<'a>(node: roxmltree::Node<'a, '_>, attribute: &str) -> Result<&'a str, XmlError>
+{
+    node.attribute(attribute).ok_or_else(|| XmlError::MissingAttribute{element: node.tag_name().name().to_string(), attribute: attribute.to_string()})
+}
+
+fn children<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = roxmltree::Node<'a, 'input>>
+{
+    node.children().filter(move |child| child.has_tag_name(name))
+}
+
+//only tiles of subset are used if given, neighbors of other tiles are skipped
+pub fn load(text: &str, subset: Option<&str>) -> Result<XmlTileset, XmlError>
+{
+    let document = roxmltree::Document::parse(text).map_err(|error| XmlError::Parse(error.to_string()))?;
+    let set = document.root_element();
+    let subset_tiles = match subset {
+        Some(subset) => {
+            let node = children(set, "subsets").flat_map(|subsets| children(subsets, "subset"))
+                .find(|node| node.attribute("name") == Some(subset))
+                .ok_or_else(|| XmlError::UnknownSubset(subset.to_string()))?;
+            Some(children(node, "tile").map(|tile| attribute(tile, "name")).collect::<Result<HashSet<&str>, XmlError>>()?)
+        },
+        None => None
+    };
+
+    //orientation of every variant under the 8 rotations and reflections, as in the original
+    let mut action = Vec::<[usize; 8]>::new();
+    let mut first = HashMap::<&str, usize>::new();
+    let mut declared = HashSet::<&str>::new();
+    let mut names = Vec::<String>::new();
+    let mut weights = Vec::<f64>::new();
+    for tile in children(set, "tiles").flat_map(|tiles| children(tiles, "tile"))
+    {
+        let name = attribute(tile, "name")?;
+        if !declared.insert(name)
+        {
+            return Err(XmlError::DuplicateTile(name.to_string()));
+        }
+        if subset_tiles.as_ref().is_some_and(|tiles| !tiles.contains(name))
+        {
+            continue;
+        }
+        let invalid = |attribute: &str, value: &str| XmlError::InvalidAttribute{element: format!("tile {}", name), attribute: attribute.to_string(), value: value.to_string()};
+        let letter = tile.attribute("symmetry").unwrap_or("X");
+        let (cardinality, a, b) = symmetry(letter).ok_or_else(|| invalid("symmetry", letter))?;
+        let weight = match tile.attribute("weight") {
+            Some(weight) => weight.parse::<f64>().ok().filter(|weight| weight.is_finite() && *weight > 0.0).ok_or_else(|| invalid("weight", weight))?,
+            None => 1.0
+        };
+        let offset = action.len();
+        first.insert(name, offset);
+        for t in 0..cardinality
+        {
+            action.push([t, a(t), a(a(t)), a(a(a(t))), b(t), b(a(t)), b(a(a(t))), b(a(a(a(t))))].map(|variant| variant + offset));
+            names.push(format!("{} {}", name, t));
+            weights.push(weight);
+        }
+    }
+    let ids: Vec<u8> = free_ids().take(action.len()).collect();
+    if ids.len() < action.len()
+    {
+        return Err(XmlError::TooManyTiles);
+    }
+
+    //pairs of variants allowed next to each other along x and along y, first one at the lower coordinate
+    let mut horizontal = HashSet::<(usize, usize)>::new();
+    let mut vertical = HashSet::<(usize, usize)>::new();
+    for neighbor in children(set, "neighbors").flat_map(|neighbors| children(neighbors, "neighbor"))
+    {
+        let variant = |side: &str| -> Result<Option<usize>, XmlError> {
+            let value = attribute(neighbor, side)?;
+            let invalid = || XmlError::InvalidAttribute{element: "neighbor".to_string(), attribute: side.to_string(), value: value.to_string()};
+            let mut parts = value.split_whitespace();
+            let name = parts.next().ok_or_else(invalid)?;
+            let orientation = match parts.next() {
+                Some(orientation) => orientation.parse::<usize>().ok().filter(|&orientation| orientation < 8).ok_or_else(invalid)?,
+                None => 0
+            };
+            if parts.next().is_some()
+            {
+                return Err(invalid());
+            }
+            match first.get(name) {
+                Some(&offset) => Ok(Some(action[offset][orientation])),
+                None if declared.contains(name) => Ok(None),
+                None => Err(XmlError::UnknownTile(name.to_string()))
+            }
+        };
+        let (Some(l), Some(r)) = (variant("left")?, variant("right")?) else {
+            continue;
+        };
+        let (d, u) = (action[l][1], action[r][1]);
+        horizontal.extend([(l, r), (action[l][6], action[r][6]), (action[r][4], action[l][4]), (action[r][2], action[l][2])]);
+        vertical.extend([(u, d), (action[d][6], action[u][6]), (action[u][4], action[d][4]), (action[d][2], action[u][2])]);
+    }
+
+    let all: HashSet<u8> = ids.iter().copied().collect();
+    let mut rules: Vec<DirectionMapping<HashSet<u8>>> = (0..=u8::MAX).map(|_| DirectionMapping::from_fn(|_| HashSet::new())).collect();
+    for (variant, &id) in ids.iter().enumerate()
+    {
+        let after = |pairs: &HashSet<(usize, usize)>| pairs.iter().filter(|pair| pair.0 == variant).map(|pair| ids[pair.1]).collect();
+        let before = |pairs: &HashSet<(usize, usize)>| pairs.iter().filter(|pair| pair.1 == variant).map(|pair| ids[pair.0]).collect();
+        rules[id as usize] = DirectionMapping::new(after(&vertical), before(&vertical), before(&horizontal), after(&horizontal), all.clone(), all.clone());
+    }
+    rules[BORDER as usize] = DirectionMapping::from_fn(|_| all.clone());
+    let mut weights_by_id = vec![0.0; u8::MAX as usize + 1];
+    for (&id, weight) in ids.iter().zip(weights)
+    {
+        weights_by_id[id as usize] = weight;
+    }
+    let names = ids.iter().copied().zip(names).collect();
+    Ok(XmlTileset{rules, weights: weights_by_id, names})
+}
+
+pub fn load_file<P: AsRef<Path>>(path: P, subset: Option<&str>) -> Result<XmlTileset, XmlError>
+{
+    load(&fs::read_to_string(path).map_err(XmlError::Io)?, subset)
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use super::direction_mapping::{DirectionMapping, OFFSETS};
+
+//cells connected by labeled directed edges
+//edge (from, label, to) means that rules[tile of from][label] lists tiles allowed in to
+pub struct Topology{
+    outgoing: Vec<Vec<(usize, usize)>>,
+    incoming: Vec<Vec<(usize, usize)>>,
+    labels: usize
+}
+
+impl Topology
+{
+    pub fn new(cells: usize, labels: usize) -> Topology
+    {
+        Topology{outgoing: vec![Vec::new(); cells], incoming: vec![Vec::new(); cells], labels}
+    }
+
+    pub fn add_edge(&mut self, from: usize, label: usize, to: usize) -> &mut Topology
+    {
+        assert!(label < self.labels, "label {} out of {} labels", label, self.labels);
+        self.outgoing[from].push((label, to));
+        self.incoming[to].push((label, from));
+        self
+    }
+
+    //edges between every pair of cells in both directions, like a sudoku row
+    pub fn add_clique(&mut self, cells: &[usize], label: usize) -> &mut Topology
+    {
+        for &from in cells
+        {
+            for &to in cells.iter().filter(|&&to| to != from)
+            {
+                self.add_edge(from, label, to);
+            }
+        }
+        self
+    }
+
+    pub fn cells(&self) -> usize
+    {
+        self.outgoing.len()
+    }
+
+    pub fn labels(&self) -> usize
+    {
+        self.labels
+    }
+
+    //(label, to) of edges leaving cell
+    pub fn outgoing(&self, cell: usize) -> &Vec<(usize, usize)>
+    {
+        &self.outgoing[cell]
+    }
+
+    //(label, from) of edges entering cell
+    pub fn incoming(&self, cell: usize) -> &Vec<(usize, usize)>
+    {
+        &self.incoming[cell]
+    }
+
+    //cells are numbered like Vec3D stores them, labels are directions in the order of OFFSETS
+    pub fn grid(x_size: usize, y_size: usize, z_size: usize) -> Topology
+    {
+        Topology::from_offsets(x_size, y_size, z_size, &OFFSETS)
+    }
+
+    //26 neighbors of a cube, labels in the order of moore_offsets
+    pub fn moore(x_size: usize, y_size: usize, z_size: usize) -> Topology
+    {
+        Topology::from_offsets(x_size, y_size, z_size, &Topology::moore_offsets())
+    }
+
+    pub fn moore_offsets() -> Vec<(isize, isize, isize)>
+    {
+        let range = || -1..=1isize;
+        range().flat_map(|x| range().flat_map(move |y| range().map(move |z| (x, y, z))))
+            .filter(|&offset| offset != (0, 0, 0))
+            .collect()
+    }
+
+    //hexagons in offset rows, odd rows are shifted right by half a cell, labels in the order of HEX_OFFSETS
+    pub fn hex(x_size: usize, y_size: usize) -> Topology
+    {
+        let mut topology = Topology::new(x_size * y_size, HEX_OFFSETS.len());
+        for y in 0..y_size
+        {
+            for x in 0..x_size
+            {
+                for (label, &(dx, dy)) in HEX_OFFSETS.iter().enumerate()
+                {
+                    //diagonal neighbors of odd rows lie one column further right
+                    let dx = if dy != 0 && y % 2 == 1 { dx + 1 } else { dx };
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if nx >= 0 && ny >= 0 && (nx as usize) < x_size && (ny as usize) < y_size
+                    {
+                        topology.add_edge(x + y * x_size, label, nx as usize + ny as usize * x_size);
+                    }
+                }
+            }
+        }
+        topology
+    }
+
+    fn from_offsets(x_size: usize, y_size: usize, z_size: usize, offsets: &[(isize, isize, isize)]) -> Topology
+    {
+        let mut topology = Topology::new(x_size * y_size * z_size, offsets.len());
+        let size = [x_size as isize, y_size as isize, z_size as isize];
+        for cell in 0..topology.cells()
+        {
+            let (x, y, z) = (cell % x_size, (cell / x_size) % y_size, cell / (x_size * y_size));
+            for (label, &(dx, dy, dz)) in offsets.iter().enumerate()
+            {
+                let neighbor = [x as isize + dx, y as isize + dy, z as isize + dz];
+                if neighbor.iter().zip(size).all(|(&coordinate, size)| coordinate >= 0 && coordinate < size)
+                {
+                    topology.add_edge(cell, label, (neighbor[0] + neighbor[1] * size[0] + neighbor[2] * size[0] * size[1]) as usize);
+                }
+            }
+        }
+        topology
+    }
+}
+
+//neighbors of a hexagon in an even row: right, left, up right, up left, down right, down left
+pub const HEX_OFFSETS: [(isize, isize); 6] = [(1, 0), (-1, 0), (0, 1), (-1, 1), (0, -1), (-1, -1)];
+
+//rules keyed by label for Topology::grid
+pub fn rules_from_mapping(rules: &[DirectionMapping<HashSet<u8>>]) -> Vec<Vec<HashSet<u8>>>
+{
+    rules.iter().map(|dir_rules| (0..OFFSETS.len()).map(|direction| dir_rules.get(direction).clone()).collect()).collect()
+}
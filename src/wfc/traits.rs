@@ -1,7 +1,230 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt};
 
-use super::{direction_mapping::DirectionMapping, vec3d::Vec3D};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
 
-pub trait WFC{
-    fn solve(problem: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> Vec3D<u8>;
-}
\ No newline at end of file
+use super::{direction_mapping::DirectionMapping, direction_mapping2d::DirectionMapping2D, vec3d::{self, Vec3D, PosIter3D}, vec2d::{self, Vec2D, PosIter2D}, tile::Tile, budget::{Budget, Meter, Metering, Unmetered, SolveError}};
+
+//shape of the cells a solver works on, positions, cell storage and per direction rules
+pub trait Grid{
+    type Position: Copy + fmt::Debug;
+    type Cells<T: Clone + fmt::Debug>: Clone + fmt::Debug;
+    type Directions<T>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Grid3D{}
+
+impl Grid for Grid3D
+{
+    type Position = (usize, usize, usize);
+    type Cells<T: Clone + fmt::Debug> = Vec3D<T>;
+    type Directions<T> = DirectionMapping<T>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Grid2D{}
+
+impl Grid for Grid2D
+{
+    type Position = (usize, usize);
+    type Cells<T: Clone + fmt::Debug> = Vec2D<T>;
+    type Directions<T> = DirectionMapping2D<T>;
+}
+
+//grid where every cell has the same directions to its neighbors, so one propagation fits all of them
+pub trait Lattice: Grid{
+    type Neighbors: IntoIterator<Item = Self::Position> + AsRef<[Self::Position]>;
+    type Positions: Iterator<Item = Self::Position>;
+    const DIRECTIONS: usize;
+    //neighbors in the order of directions
+    fn neighbors(position: Self::Position) -> Self::Neighbors;
+    //direction of the rule which each neighbor uses to constrain the cell, in the order of neighbors
+    fn constraining_direction(direction: usize) -> usize;
+    fn direction<T>(rules: &Self::Directions<T>, direction: usize) -> &T;
+    fn map_directions<T, U, F: FnMut(&T) -> U>(rules: &Self::Directions<T>, f: F) -> Self::Directions<U>;
+    fn positions<T: Clone + fmt::Debug>(cells: &Self::Cells<T>) -> Self::Positions;
+    //cells of the same size as like, all holding init
+    fn new_cells<T: Clone + fmt::Debug, U: Clone + fmt::Debug>(like: &Self::Cells<U>, init: T) -> Self::Cells<T>;
+    fn get<T: Clone + fmt::Debug>(cells: &Self::Cells<T>, position: Self::Position) -> T;
+    fn set<T: Clone + fmt::Debug>(cells: &mut Self::Cells<T>, position: Self::Position, value: T);
+    fn is_border<T: Clone + fmt::Debug>(cells: &Self::Cells<T>, position: Self::Position) -> bool;
+}
+
+impl Lattice for Grid3D
+{
+    type Neighbors = [(usize, usize, usize); 6];
+    type Positions = PosIter3D;
+    const DIRECTIONS: usize = 6;
+
+    fn neighbors(position: (usize, usize, usize)) -> [(usize, usize, usize); 6]
+    {
+        vec3d::neighbors(position)
+    }
+
+    fn constraining_direction(direction: usize) -> usize
+    {
+        vec3d::CONSTRAINING_DIRECTION[direction]
+    }
+
+    fn direction<T>(rules: &DirectionMapping<T>, direction: usize) -> &T
+    {
+        rules.get(direction)
+    }
+
+    fn map_directions<T, U, F: FnMut(&T) -> U>(rules: &DirectionMapping<T>, mut f: F) -> DirectionMapping<U>
+    {
+        DirectionMapping::new(f(rules.up()), f(rules.down()), f(rules.right()), f(rules.left()), f(rules.front()), f(rules.back()))
+    }
+
+    fn positions<T: Clone + fmt::Debug>(cells: &Vec3D<T>) -> PosIter3D
+    {
+        PosIter3D::new(cells)
+    }
+
+    fn new_cells<T: Clone + fmt::Debug, U: Clone + fmt::Debug>(like: &Vec3D<U>, init: T) -> Vec3D<T>
+    {
+        Vec3D::new(like.x_size, like.y_size, like.z_size, init)
+    }
+
+    fn get<T: Clone + fmt::Debug>(cells: &Vec3D<T>, (x, y, z): (usize, usize, usize)) -> T
+    {
+        cells.get(x, y, z)
+    }
+
+    fn set<T: Clone + fmt::Debug>(cells: &mut Vec3D<T>, (x, y, z): (usize, usize, usize), value: T)
+    {
+        cells.set(x, y, z, value)
+    }
+
+    fn is_border<T: Clone + fmt::Debug>(cells: &Vec3D<T>, (x, y, z): (usize, usize, usize)) -> bool
+    {
+        cells.is_border(x, y, z)
+    }
+}
+
+impl Lattice for Grid2D
+{
+    type Neighbors = [(usize, usize); 4];
+    type Positions = PosIter2D;
+    const DIRECTIONS: usize = 4;
+
+    fn neighbors(position: (usize, usize)) -> [(usize, usize); 4]
+    {
+        vec2d::neighbors(position)
+    }
+
+    fn constraining_direction(direction: usize) -> usize
+    {
+        vec2d::CONSTRAINING_DIRECTION[direction]
+    }
+
+    fn direction<T>(rules: &DirectionMapping2D<T>, direction: usize) -> &T
+    {
+        rules.get(direction)
+    }
+
+    fn map_directions<T, U, F: FnMut(&T) -> U>(rules: &DirectionMapping2D<T>, mut f: F) -> DirectionMapping2D<U>
+    {
+        DirectionMapping2D::new(f(rules.up()), f(rules.down()), f(rules.right()), f(rules.left()))
+    }
+
+    fn positions<T: Clone + fmt::Debug>(cells: &Vec2D<T>) -> PosIter2D
+    {
+        PosIter2D::new(cells)
+    }
+
+    fn new_cells<T: Clone + fmt::Debug, U: Clone + fmt::Debug>(like: &Vec2D<U>, init: T) -> Vec2D<T>
+    {
+        Vec2D::new(like.x_size, like.y_size, init)
+    }
+
+    fn get<T: Clone + fmt::Debug>(cells: &Vec2D<T>, (x, y): (usize, usize)) -> T
+    {
+        cells.get(x, y)
+    }
+
+    fn set<T: Clone + fmt::Debug>(cells: &mut Vec2D<T>, (x, y): (usize, usize), value: T)
+    {
+        cells.set(x, y, value)
+    }
+
+    fn is_border<T: Clone + fmt::Debug>(cells: &Vec2D<T>, (x, y): (usize, usize)) -> bool
+    {
+        cells.is_border(x, y)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Contradiction<T: Clone + fmt::Debug = u8, G: Grid = Grid3D>{
+    //cell which was left without any legal tile
+    pub position: G::Position,
+    //neighbor whose constraint removed the last candidate
+    pub neighbor: G::Position,
+    //number of collapses made before contradiction was found
+    pub step: usize,
+    //solution at the moment of contradiction, cells without legal tiles hold EMPTY
+    pub partial: G::Cells<T>
+}
+
+pub type Contradiction2D = Contradiction<u8, Grid2D>;
+
+impl<T: Tile> Contradiction<T>
+{
+    pub fn new(position: (usize, usize, usize), neighbor: (usize, usize, usize), step: usize, partial: Vec3D<T>) -> Contradiction<T>
+    {
+        Contradiction{position, neighbor, step, partial}
+    }
+}
+
+impl<T: Tile, G: Grid> fmt::Display for Contradiction<T, G>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "contradiction at {:?} caused by neighbor {:?} after {} steps", self.position, self.neighbor, self.step)
+    }
+}
+
+impl<T: Tile, G: Grid + fmt::Debug> std::error::Error for Contradiction<T, G> {}
+
+pub trait WFC<G: Grid = Grid3D>{
+    //meter is asked before every collapse and for every cell taken from propagation queue, solve stops once it refuses
+    fn try_solve_metered<M: Metering>(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<G::Cells<u8>, SolveError<G>>;
+
+    fn try_solve_with_rng(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, rng: &mut dyn RngCore) -> Result<G::Cells<u8>, Contradiction<u8, G>>
+    {
+        Self::try_solve_metered(problem, rules, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)
+    }
+
+    //stops once budget runs out, cancellation is checked while propagating
+    fn try_solve_with_budget(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, budget: &Budget, rng: &mut dyn RngCore) -> Result<G::Cells<u8>, SolveError<G>>
+    {
+        Self::try_solve_metered(problem, rules, &mut Meter::new(budget), rng)
+    }
+
+    fn try_solve(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>) -> Result<G::Cells<u8>, Contradiction<u8, G>>
+    {
+        Self::try_solve_with_rng(problem, rules, &mut rand::thread_rng())
+    }
+
+    fn try_solve_seeded(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, seed: u64) -> Result<G::Cells<u8>, Contradiction<u8, G>>
+    {
+        Self::try_solve_with_rng(problem, rules, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn solve_with_rng(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, rng: &mut dyn RngCore) -> G::Cells<u8>
+    {
+        match Self::try_solve_with_rng(problem, rules, rng){
+            Ok(solution) => solution,
+            Err(contradiction) => contradiction.partial
+        }
+    }
+
+    fn solve(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>) -> G::Cells<u8>
+    {
+        Self::solve_with_rng(problem, rules, &mut rand::thread_rng())
+    }
+
+    fn solve_seeded(problem: &G::Cells<u8>, rules: &Vec<G::Directions<HashSet<u8>>>, seed: u64) -> G::Cells<u8>
+    {
+        Self::solve_with_rng(problem, rules, &mut StdRng::seed_from_u64(seed))
+    }
+}
@@ -1,8 +1,139 @@
-use rand::Rng;
+use std::{collections::HashSet, hash::Hash};
+
+use rand::{Rng, RngCore};
+
+use super::{tile::Tile, vec3d::{Vec3D, PosIter3D}};
+
+//noise added to entropy so that ties are broken randomly instead of by position
+pub const ENTROPY_NOISE: f64 = 1e-6;
 
 pub fn get_random<T:Copy, I:IntoIterator<Item = T>>(from: I) -> T
+{
+    get_random_with(&mut rand::thread_rng(), from)
+}
+
+pub fn get_random_with<T:Copy, I:IntoIterator<Item = T>>(rng: &mut dyn RngCore, from: I) -> T
 {
     let members_vec = Vec::<T>::from_iter(from);
-    let random = rand::thread_rng().gen_range(0..members_vec.len());
-    return members_vec[random]
-}
\ No newline at end of file
+    let random = rng.gen_range(0..members_vec.len());
+    members_vec[random]
+}
+
+//picks item with probability proportional to its weight, uniformly if all weights are zero
+pub fn get_weighted_random_with<T:Tile, I:IntoIterator<Item = T>>(rng: &mut dyn RngCore, from: I, weights: &[f64]) -> T
+{
+    let members_vec = Vec::<T>::from_iter(from);
+    let total: f64 = members_vec.iter().map(|&item| weights[item.index()]).sum();
+    if total <= 0.0
+    {
+        return get_random_with(rng, members_vec);
+    }
+    let mut random = rng.gen_range(0.0..total);
+    for &item in &members_vec
+    {
+        random -= weights[item.index()];
+        if random < 0.0
+        {
+            return item;
+        }
+    }
+    //rounding left some weight unused, falling back to the last item which could be picked
+    *members_vec.iter().rfind(|&&item| weights[item.index()] > 0.0).unwrap()
+}
+
+//Shannon entropy of the weights of tiles, tiles with zero weight are ignored
+//if every weight is zero tiles are picked uniformly, so entropy is the one of uniform weights
+pub fn entropy<I:IntoIterator<Item = usize>>(tiles: I, weights: &[f64]) -> f64
+{
+    let (count, sum, sum_log) = tiles.into_iter()
+        .map(|tile| weights[tile])
+        .fold((0, 0.0, 0.0), |(count, sum, sum_log), weight| {
+            if weight > 0.0
+            {
+                (count + 1, sum + weight, sum_log + weight * weight.ln())
+            }
+            else
+            {
+                (count + 1, sum, sum_log)
+            }
+        });
+    if sum <= 0.0
+    {
+        return (count as f64).ln();
+    }
+    sum.ln() - sum_log / sum
+}
+
+//undecided cell whose candidate tiles have the lowest entropy
+pub fn find_minimal_entropy<C:Clone, I:IntoIterator<Item = usize>, F:Fn(&C) -> I>(solution: &Vec3D<C>, tiles: F, weights: &[f64], rng: &mut dyn RngCore) -> Option<(usize, usize, usize)>
+{
+    let mut minimal = None;
+    let mut minimal_entropy = f64::MAX;
+    for (x, y, z) in PosIter3D::new(solution)
+    {
+        let current: Vec<usize> = tiles(solution.get_ref(x, y, z)).into_iter().collect();
+        if current.len() > 1
+        {
+            let entropy = entropy(current, weights) + rng.gen_range(0.0..ENTROPY_NOISE);
+            if entropy < minimal_entropy
+            {
+                minimal_entropy = entropy;
+                minimal = Some((x, y, z));
+            }
+        }
+    }
+    minimal
+}
+
+//candidates of a cell as each solver stores them
+pub trait Domain{
+    fn intersect_with(& mut self, other: &Self);
+    fn is_empty(&self) -> bool;
+}
+
+impl Domain for Vec<bool>
+{
+    fn intersect_with(& mut self, other: &Self)
+    {
+        for (a, &b) in self.iter_mut().zip(other)
+        {
+            *a = *a && b;
+        }
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        !self.contains(&true)
+    }
+}
+
+impl<T: Eq + Hash> Domain for HashSet<T>
+{
+    fn intersect_with(& mut self, other: &Self)
+    {
+        self.retain(|item| other.contains(item));
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        HashSet::is_empty(self)
+    }
+}
+
+//first neighbor after which intersection of constraints becomes empty, position if it never does
+pub fn wiping_neighbor<D:Domain, P, I:IntoIterator<Item = (D, P)>>(constraints: I, position: P) -> P
+{
+    let mut constraints = constraints.into_iter();
+    let Some((mut legal, mut neighbor)) = constraints.next() else {
+        return position;
+    };
+    while !legal.is_empty()
+    {
+        let Some((constraint, next)) = constraints.next() else {
+            return position;
+        };
+        legal.intersect_with(&constraint);
+        neighbor = next;
+    }
+    neighbor
+}
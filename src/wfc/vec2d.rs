@@ -0,0 +1,88 @@
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Vec2D<T>{
+    repr: Vec::<T>,
+    pub x_size: usize,
+    pub y_size: usize,
+}
+
+impl<T:Clone> Vec2D<T>{
+    pub fn new(x_size: usize, y_size: usize, init: T) -> Vec2D<T>
+    {
+        Vec2D{
+            repr: vec![init; x_size * y_size],
+            x_size,
+            y_size,
+        }
+    }
+    pub fn with_borders(x_size: usize, y_size: usize, interior: T, border: T) -> Vec2D<T>
+    {
+        let mut a = Vec2D::new(x_size, y_size, interior);
+        for (x, y) in PosIter2D::new(&a){
+            if a.is_border(x, y){
+                a.set(x, y, border.clone());
+            }
+        }
+        a
+    }
+    pub fn get(&self, x: usize, y: usize) -> T
+    {
+        self.repr[x + y * self.x_size].clone()
+    }
+    pub fn set(&mut self, x: usize, y: usize, val: T)
+    {
+        self.repr[x + y * self.x_size] = val
+    }
+    pub fn is_border(&self, x: usize, y: usize) -> bool
+    {
+        x == 0 || y == 0 || x == self.x_size - 1 || y == self.y_size - 1
+    }
+}
+
+//neighbors in the same order as constraints in legal_tiles
+pub fn neighbors((x, y): (usize, usize)) -> [(usize, usize); 4]
+{
+    [(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)]
+}
+
+//direction of the rule which each neighbor uses to constrain the cell, in the order of neighbors
+pub const CONSTRAINING_DIRECTION: [usize; 4] = [1, 0, 3, 2];
+
+pub struct PosIter2D
+{
+    size: (usize, usize),
+    i: usize,
+    no_border: bool
+}
+
+impl PosIter2D
+{
+    pub fn new<T>(from: &Vec2D<T>) -> PosIter2D
+    {
+        PosIter2D {size: (from.x_size, from.y_size), i: 0, no_border: false}
+    }
+
+    pub fn new_no_border<T>(from: &Vec2D<T>) -> PosIter2D
+    {
+        PosIter2D {size: (from.x_size-2, from.y_size-2), i: 0, no_border: true}
+    }
+}
+impl Iterator for PosIter2D
+{
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x_size, y_size) = self.size;
+        if self.i < x_size * y_size{
+            self.i += 1;
+            let (x, y) = ((self.i - 1) % x_size, (self.i - 1) / x_size);
+            if self.no_border{
+                Some((x + 1, y + 1))
+            }
+            else {
+                Some((x, y))
+            }
+        }
+        else {
+            None
+        }
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Vec3D<T>{
     repr: Vec::<T>,
     pub x_size: usize,
@@ -19,14 +19,9 @@ impl<T:Clone> Vec3D<T>{
     pub fn with_borders(x_size: usize, y_size: usize, z_size: usize, interior: T, border: T) -> Vec3D<T>
     {
         let mut a = Vec3D::new(x_size, y_size, z_size, interior);
-        for i in 0..x_size{
-            for j in 0..y_size{
-                a.set(i, j, 0, border.clone());
-                a.set(i, j, z_size-1, border.clone());
-                a.set(0, i, j, border.clone());
-                a.set(x_size-1, i, j, border.clone());
-                a.set(i, 0, j, border.clone());
-                a.set(i, y_size-1, j, border.clone());
+        for (x, y, z) in PosIter3D::new(&a){
+            if a.is_border(x, y, z){
+                a.set(x, y, z, border.clone());
             }
         }
         a
@@ -35,12 +30,30 @@ impl<T:Clone> Vec3D<T>{
     {
         self.repr[x + (y * self.x_size) + (z * self.x_size * self.y_size)].clone()
     }
+    //borrowing access for cells which are expensive to clone
+    pub fn get_ref(&self, x: usize, y: usize, z: usize) -> &T
+    {
+        &self.repr[x + (y * self.x_size) + (z * self.x_size * self.y_size)]
+    }
     pub fn set(&mut self, x: usize, y: usize, z: usize, val: T)
     {
         self.repr[x + (y * self.x_size) + (z * self.x_size * self.y_size)] = val
     }
+    pub fn is_border(&self, x: usize, y: usize, z: usize) -> bool
+    {
+        x == 0 || y == 0 || z == 0 || x == self.x_size - 1 || y == self.y_size - 1 || z == self.z_size - 1
+    }
 }
 
+//neighbors in the same order as constraints in legal_tiles
+pub fn neighbors((x, y, z): (usize, usize, usize)) -> [(usize, usize, usize); 6]
+{
+    [(x, y + 1, z), (x, y - 1, z), (x - 1, y, z), (x + 1, y, z), (x, y, z - 1), (x, y, z + 1)]
+}
+
+//direction of the rule which each neighbor uses to constrain the cell, in the order of neighbors
+pub const CONSTRAINING_DIRECTION: [usize; 6] = [1, 0, 3, 2, 4, 5];
+
 pub struct PosIter3D
 {
     size: (usize, usize, usize),
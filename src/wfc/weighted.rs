@@ -0,0 +1,113 @@
+use std::{collections::{HashSet, VecDeque}, fmt};
+
+use rand::RngCore;
+
+use super::{direction_mapping::DirectionMapping, rules, utils, tile::Tile, bits256_set::Bits256Set, queueprop_bitarrayset::QueuePropBitArraySet, vec3d::{Vec3D, neighbors}, traits::{WFC, Contradiction}, budget::{Metering, Unmetered, SolveError}};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CellSelection{
+    //cell with the fewest candidates
+    MinimumRemaining,
+    //cell with the lowest Shannon entropy of candidate weights
+    Entropy
+}
+
+#[derive(Clone, Debug)]
+pub enum WeightedError<T: Tile = u8>{
+    //every tile which has rules needs a weight
+    MissingWeights{tiles: usize, weights: usize},
+    //weights have to be finite and not negative
+    InvalidWeight{tile: usize},
+    Contradiction(Contradiction<T>)
+}
+
+impl<T: Tile> fmt::Display for WeightedError<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightedError::MissingWeights{tiles, weights} => write!(f, "rules have {} tiles but only {} weights are given", tiles, weights),
+            WeightedError::InvalidWeight{tile} => write!(f, "weight of tile {} is negative or not finite", tile),
+            WeightedError::Contradiction(contradiction) => contradiction.fmt(f)
+        }
+    }
+}
+
+impl<T: Tile> std::error::Error for WeightedError<T> {}
+
+impl<T: Tile> From<Contradiction<T>> for WeightedError<T>
+{
+    fn from(contradiction: Contradiction<T>) -> Self {
+        WeightedError::Contradiction(contradiction)
+    }
+}
+
+pub struct Weighted{}
+
+impl Weighted
+{
+    //weights are indexed by tile like rules, tiles with zero weight are only chosen if nothing else is left
+    pub fn try_solve_weighted(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], weights: &[f64], selection: CellSelection, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, WeightedError>
+    {
+        Weighted::check_weights(rules.len(), weights)?;
+        Ok(Weighted::solve(map, rules, weights, selection, &mut Unmetered{}, rng).map_err(SolveError::into_contradiction)?)
+    }
+
+    //every tile which has rules needs a weight, weights have to be finite and not negative
+    pub(crate) fn check_weights<T: Tile>(tiles: usize, weights: &[f64]) -> Result<(), WeightedError<T>>
+    {
+        if weights.len() < tiles
+        {
+            return Err(WeightedError::MissingWeights{tiles, weights: weights.len()});
+        }
+        match weights.iter().position(|weight| !weight.is_finite() || *weight < 0.0) {
+            Some(tile) => Err(WeightedError::InvalidWeight{tile}),
+            None => Ok(())
+        }
+    }
+
+    fn solve<M: Metering>(map: &Vec3D<u8>, rules: &[DirectionMapping<HashSet<u8>>], weights: &[f64], selection: CellSelection, meter: & mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        let rules_internal = QueuePropBitArraySet::prepare_rules(rules);
+        let mut solution = QueuePropBitArraySet::prepare_map_metered(map, &rules_internal, meter)?;
+        Weighted::collapse(&mut solution, &rules_internal, weights, selection, meter, rng)?;
+        Ok(QueuePropBitArraySet::format_solution(&solution))
+    }
+
+    //collapses every undecided cell of the wave
+    fn collapse<M: Metering>(solution: & mut Vec3D<Bits256Set>, rules: &[DirectionMapping<Bits256Set>], weights: &[f64], selection: CellSelection, meter: & mut M, rng: &mut dyn RngCore) -> Result<(), SolveError>
+    {
+        let mut step = 0;
+        loop {
+            let minimal = match selection {
+                CellSelection::MinimumRemaining => QueuePropBitArraySet::find_minimal(solution),
+                CellSelection::Entropy => utils::find_minimal_entropy(solution, |candidates| candidates.items().into_iter().map(usize::from), weights, rng)
+                    .map(|(x, y, z)| ((x, y, z), solution.get(x, y, z)))
+            };
+            match minimal {
+                Some(((x, y, z), current)) => {
+                    if let Err(reason) = meter.collapse()
+                    {
+                        return Err(SolveError::Stopped{reason, step, partial: QueuePropBitArraySet::format_partial(solution)});
+                    }
+                    //minimal found setting it randomly according to weights
+                    solution.set(x, y, z, Bits256Set::new_from_vec(vec![utils::get_weighted_random_with(rng, current.items(), weights)]));
+                    step += 1;
+                    let mut to_propagate = VecDeque::from(neighbors((x, y, z)));
+                    QueuePropBitArraySet::propagate_metered(solution, rules, &mut to_propagate, step, meter)?;
+                },
+                None => {
+                    //nothing left to be collapsed
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+//uniform weights and the same cell selection as the other solvers
+impl WFC for Weighted{
+    fn try_solve_metered<M: Metering>(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, meter: &mut M, rng: &mut dyn RngCore) -> Result<Vec3D<u8>, SolveError>
+    {
+        Weighted::solve(map, rules, &rules::get_uniform_weights(), CellSelection::MinimumRemaining, meter, rng)
+    }
+}
@@ -1,6 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use wfc::wfc::{vec3d::Vec3D, direction_mapping::DirectionMapping, baseline, traits::WFC, rules, queueprop, queueprop_bitarrayset, queueprop_bitarrayset_fibheap, stackprop};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+use wfc::wfc::{vec3d::{self, Vec3D, PosIter3D}, vec2d::{Vec2D, PosIter2D}, direction_mapping::DirectionMapping, direction_mapping2d::DirectionMapping2D, baseline, traits::WFC, rules, queueprop, queueprop_bitarrayset, queueprop_bitarrayset_fibheap, queueprop_dynset, stackprop, simple, ac4, backtracking, bounded, boundary, budget, chunks, connectivity, counts, graph, topology, weighted, stepper, overlapping, rules_builder, rules_check, snapshot, symmetry, tileset, tileset_toml, tileset_xml, utils};
 
 fn validate(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> bool
 {
@@ -37,6 +39,25 @@ fn validate(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>) -> bool
     true
 }
 
+//runs a check which is generic over the solver for every solver implementing WFC
+macro_rules! check_solvers {
+    ($check:ident($($arg:expr),*)) => {
+        $check::<simple::Simple>($($arg),*);
+        $check::<baseline::BaseLine>($($arg),*);
+        $check::<queueprop::QueueProp>($($arg),*);
+        $check::<stackprop::StackProp>($($arg),*);
+        $check::<queueprop_bitarrayset::QueuePropBitArraySet>($($arg),*);
+        $check::<queueprop_bitarrayset_fibheap::QueuePropBitArraySetFibHeap>($($arg),*);
+        $check::<backtracking::BackTracking>($($arg),*);
+        $check::<weighted::Weighted>($($arg),*);
+        $check::<queueprop_dynset::QueuePropDynSet>($($arg),*);
+        $check::<bounded::Bounded>($($arg),*);
+        $check::<ac4::AC4>($($arg),*);
+        #[cfg(feature = "parallel")]
+        $check::<wfc::wfc::parallel::Parallel<queueprop_bitarrayset::QueuePropBitArraySet>>($($arg),*);
+    };
+}
+
 #[test]
 fn test_baseline(){
     let size:usize = 8;
@@ -80,4 +101,907 @@ fn test_queueprop_bitarrayset_fibheap(){
     let rules = rules::get_pipes_rules();
     let solution = queueprop_bitarrayset_fibheap::QueuePropBitArraySetFibHeap::solve(&map, &rules);
     assert!(validate(&solution, &rules));
-}
\ No newline at end of file
+}
+
+fn check_contradiction<T: WFC>()
+{
+    let size:usize = 5;
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    //red pipe going up below green pipe going down leaves nothing legal in between
+    let red_up = 0b1000_0000;
+    map.set(2, 1, 2, red_up);
+    map.set(2, 3, 2, 0b0100_0001);
+    let mut rules = rules::get_pipes_rules();
+    //pipe rules accept BORDER everywhere, forbid it above the red pipe
+    let red_up_rules = &rules[red_up as usize];
+    let above: HashSet<u8> = red_up_rules.up().iter().cloned().filter(|&tile| tile != rules::BORDER).collect();
+    rules[red_up as usize] = DirectionMapping::new(
+        above,
+        red_up_rules.down().clone(),
+        red_up_rules.right().clone(),
+        red_up_rules.left().clone(),
+        red_up_rules.front().clone(),
+        red_up_rules.back().clone());
+    let contradiction = T::try_solve(&map, &rules).unwrap_err();
+    assert_eq!(contradiction.position, (2, 2, 2));
+    assert_eq!(contradiction.neighbor, (2, 1, 2));
+    assert_eq!(contradiction.step, 0);
+    assert_eq!(contradiction.partial.get(2, 2, 2), rules::EMPTY);
+}
+
+#[test]
+fn test_contradiction(){
+    check_solvers!(check_contradiction());
+}
+
+fn check_tile_255<T: WFC>()
+{
+    //tiles 0 and 255 alternate like a checkerboard, unknown EMPTY neighbors accept both
+    let mut rules: Vec<DirectionMapping<HashSet<u8>>> = (0..=u8::MAX).map(|_| DirectionMapping::from_fn(|_| HashSet::new())).collect();
+    rules[0] = DirectionMapping::from_fn(|_| HashSet::from([u8::MAX]));
+    rules[u8::MAX as usize] = DirectionMapping::from_fn(|_| HashSet::from([0]));
+    rules[rules::EMPTY as usize] = DirectionMapping::from_fn(|_| HashSet::from([0, u8::MAX]));
+    rules[rules::BORDER as usize] = DirectionMapping::from_fn(|_| HashSet::from([0, u8::MAX]));
+    let mut map = Vec3D::with_borders(5, 5, 5, rules::EMPTY, rules::BORDER);
+    map.set(1, 1, 1, u8::MAX);
+    let solution = T::try_solve_seeded(&map, &rules, 42).unwrap();
+    for (x, y, z) in PosIter3D::new_no_border(&solution)
+    {
+        assert_eq!(solution.get(x, y, z), if (x + y + z) % 2 == 1 { u8::MAX } else { 0 });
+    }
+}
+
+#[test]
+fn test_tile_255(){
+    check_solvers!(check_tile_255());
+}
+
+//only pipe segments connecting exactly two directions, random collapse runs into contradictions often
+fn pipe_segments_rules() -> Vec<DirectionMapping<HashSet<u8>>>
+{
+    let is_segment = |tile: u8| tile != rules::BORDER && (tile & 0b1111_1100).count_ones() == 2;
+    let segments = |set: &HashSet<u8>| set.iter().cloned().filter(|&tile| is_segment(tile)).collect::<HashSet<u8>>();
+    rules::get_pipes_rules().iter().enumerate().map(|(tile, dir_rules)| {
+        let tile = tile as u8;
+        if tile == rules::BORDER || tile == rules::EMPTY
+        {
+            DirectionMapping::new(dir_rules.up().clone(), dir_rules.down().clone(), dir_rules.right().clone(), dir_rules.left().clone(), dir_rules.front().clone(), dir_rules.back().clone())
+        }
+        else if is_segment(tile)
+        {
+            DirectionMapping::new(segments(dir_rules.up()), segments(dir_rules.down()), segments(dir_rules.right()), segments(dir_rules.left()), segments(dir_rules.front()), segments(dir_rules.back()))
+        }
+        else
+        {
+            DirectionMapping::new(HashSet::new(), HashSet::new(), HashSet::new(), HashSet::new(), HashSet::new(), HashSet::new())
+        }
+    }).collect()
+}
+
+#[test]
+fn test_backtracking(){
+    let size:usize = 8;
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    map.set(3, 3, 3, 0b1001_0001);
+    let rules = pipe_segments_rules();
+    let solution = backtracking::BackTracking::try_solve(&map, &rules).unwrap();
+    assert!(validate(&solution, &rules));
+    let solution = backtracking::BackTracking::try_solve_with_limits(&map, &rules, 4, backtracking::DEFAULT_MAX_BACKTRACKS, &mut StdRng::seed_from_u64(2)).unwrap();
+    assert!(validate(&solution, &rules));
+}
+
+fn check_seeded<T: WFC>(size: usize)
+{
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let solution = T::solve_seeded(&map, &rules, 42);
+    assert!(validate(&solution, &rules));
+    assert_eq!(solution, T::solve_seeded(&map, &rules, 42));
+    assert_ne!(solution, T::solve_seeded(&map, &rules, 43));
+}
+
+#[test]
+fn test_seeded(){
+    check_solvers!(check_seeded(4));
+}
+
+#[test]
+fn test_seeded_queue_stack(){
+    //propagation order does not change the outcome of the same sequence of choices
+    let size:usize = 6;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let queue_solution = queueprop::QueueProp::solve_seeded(&map, &rules, 7);
+    let stack_solution = stackprop::StackProp::solve_seeded(&map, &rules, 7);
+    assert_eq!(queue_solution, stack_solution);
+}
+
+#[test]
+fn test_weighted(){
+    let size:usize = 8;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let solution = weighted::Weighted::solve_seeded(&map, &rules, 3);
+    assert!(validate(&solution, &rules));
+    //only red pipe without connections has weight, so nothing else gets chosen
+    let mut weights = vec![0.0; u8::MAX as usize + 1];
+    weights[0] = 1.0;
+    for selection in [weighted::CellSelection::MinimumRemaining, weighted::CellSelection::Entropy]
+    {
+        let solution = weighted::Weighted::try_solve_weighted(&map, &rules, &weights, selection, &mut StdRng::seed_from_u64(3)).unwrap();
+        assert!(validate(&solution, &rules));
+        for (x, y, z) in PosIter3D::new_no_border(&solution)
+        {
+            assert_eq!(solution.get(x, y, z), 0);
+        }
+    }
+    //every tile with rules needs a weight
+    let short = weighted::Weighted::try_solve_weighted(&map, &rules, &vec![1.0; 10], weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(3));
+    assert!(matches!(short, Err(weighted::WeightedError::MissingWeights{tiles: 256, weights: 10})));
+    for invalid in [f64::NAN, f64::INFINITY, -1.0]
+    {
+        weights[5] = invalid;
+        let error = weighted::Weighted::try_solve_weighted(&map, &rules, &weights, weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(3));
+        assert!(matches!(error, Err(weighted::WeightedError::InvalidWeight{tile: 5})));
+    }
+    //cells whose candidates all have zero weight are as uncertain as with uniform weights
+    assert_eq!(utils::entropy([1, 2], &[1.0, 0.0, 0.0]), utils::entropy([0, 1], &[1.0, 1.0]));
+}
+
+#[test]
+fn test_stepper(){
+    let size:usize = 8;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let mut solver = stepper::Stepper::new(&map, &rules, StdRng::seed_from_u64(5));
+    let mut collapsed = Vec::new();
+    let mut events = 0;
+    while let Some(event) = solver.next()
+    {
+        events += 1;
+        match event
+        {
+            stepper::StepEvent::Collapsed{position, tile} => {
+                assert_eq!(solver.wave().get(position.0, position.1, position.2).items(), vec![tile]);
+                collapsed.push((position, tile));
+            },
+            stepper::StepEvent::Propagated{changed_cells} => {
+                for (x, y, z) in changed_cells
+                {
+                    assert!(solver.wave().get(x, y, z).len() >= 1);
+                }
+            },
+            stepper::StepEvent::Contradiction(contradiction) => panic!("{}", contradiction),
+            stepper::StepEvent::Done => assert!(solver.is_finished())
+        }
+    }
+    assert!(events > collapsed.len());
+    assert_eq!(solver.steps(), collapsed.len());
+    let solution = solver.solution();
+    assert!(validate(&solution, &rules));
+    for ((x, y, z), tile) in collapsed
+    {
+        assert_eq!(solution.get(x, y, z), tile);
+    }
+    //stepping makes the same choices as solving in one go
+    assert_eq!(solution, queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&map, &rules, 5));
+}
+
+fn check_overlapping(symmetry: symmetry::Symmetry)
+{
+    //columns of stacked blocks standing on the floor
+    let mut sample = Vec3D::new(6, 6, 6, 0u8);
+    for (x, y, z) in PosIter3D::new(&sample)
+    {
+        let value = if y == 0 { 1 } else if x % 3 == 0 && z % 3 == 0 { 2 + (y % 2) as u8 } else { 0 };
+        sample.set(x, y, z, value);
+    }
+    let n = 2;
+    let model = overlapping::OverlappingModel::new(&sample, n, symmetry).unwrap();
+    assert_eq!(model.rules().len(), model.patterns().len() + 1);
+    let solution = model.try_solve_seeded(8, 5, 7, 11).unwrap();
+    assert_eq!((solution.x_size, solution.y_size, solution.z_size), (8, 5, 7));
+    //every window of the output has to be one of the patterns
+    for (x, y, z) in PosIter3D::new(&solution)
+    {
+        if x + n > solution.x_size || y + n > solution.y_size || z + n > solution.z_size
+        {
+            continue;
+        }
+        let mut window = Vec3D::new(n, n, n, 0u8);
+        for (i, j, k) in PosIter3D::new(&window)
+        {
+            window.set(i, j, k, solution.get(x + i, y + j, z + k));
+        }
+        assert!(model.patterns().contains(&window));
+    }
+}
+
+#[test]
+fn test_overlapping(){
+    check_overlapping(symmetry::Symmetry::None);
+    check_overlapping(symmetry::Symmetry::AroundY);
+    check_overlapping(symmetry::Symmetry::Full);
+}
+
+#[test]
+fn test_rules_builder(){
+    let mut line = Vec3D::new(3, 1, 1, rules::EMPTY);
+    line.set(0, 0, 0, 1);
+    line.set(1, 0, 0, 2);
+    let builder = rules_builder::RulesBuilder::from_example(&line);
+    let learned = builder.rules();
+    assert_eq!(learned[1].left(), &HashSet::from([2]));
+    assert_eq!(learned[2].right(), &HashSet::from([1]));
+    assert!(learned[1].right().is_empty() && learned[2].left().is_empty());
+    assert_eq!(learned[rules::BORDER as usize].up(), &HashSet::from([1, 2]));
+    assert_eq!(builder.frequencies()[1], 1.0);
+
+    //learning from a generated pipe map gives rules which the map itself satisfies
+    let size:usize = 6;
+    let example = queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER), &rules::get_pipes_rules(), 13);
+    let builder = rules_builder::RulesBuilder::from_example(&example);
+    let learned = builder.rules();
+    assert!(validate(&example, &learned));
+    let size:usize = 10;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let solution = weighted::Weighted::try_solve_weighted(&map, &learned, &builder.frequencies(), weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(13)).unwrap();
+    assert!(validate(&solution, &learned));
+    for (x, y, z) in PosIter3D::new(&solution)
+    {
+        assert!(builder.frequencies()[solution.get(x, y, z) as usize] > 0.0);
+    }
+}
+
+fn faces(up: &str, down: &str, right: &str, left: &str, front: &str, back: &str) -> DirectionMapping<String>
+{
+    DirectionMapping::new(up.to_string(), down.to_string(), right.to_string(), left.to_string(), front.to_string(), back.to_string())
+}
+
+//empty cells with straight and corner pipes, the same tiles as tilesets/pipes.toml
+fn pipe_tileset() -> tileset::Tileset
+{
+    let mut tiles = tileset::Tileset::new();
+    tiles.add_tile("empty", faces("none", "none", "none", "none", "none", "none"), symmetry::Symmetry::None, 1.0)
+        .add_tile("straight", faces("none", "none", "pipe", "pipe", "none", "none"), symmetry::Symmetry::Full, 1.0)
+        .add_tile("corner", faces("none", "none", "pipe", "none", "none", "pipe"), symmetry::Symmetry::Full, 1.0);
+    tiles
+}
+
+#[test]
+fn test_tileset(){
+    let count = |symmetry: symmetry::Symmetry, connectors: DirectionMapping<String>| {
+        let mut tiles = tileset::Tileset::new();
+        tiles.add_tile("tile", connectors, symmetry, 1.0);
+        tiles.variants().unwrap().len()
+    };
+    assert_eq!(count(symmetry::Symmetry::None, faces("none", "none", "pipe", "none", "none", "pipe")), 1);
+    assert_eq!(count(symmetry::Symmetry::AroundY, faces("none", "none", "pipe", "none", "none", "pipe")), 4);
+    assert_eq!(count(symmetry::Symmetry::Full, faces("none", "none", "pipe", "none", "none", "pipe")), 12);
+    assert_eq!(count(symmetry::Symmetry::AroundY, faces("none", "none", "pipe", "pipe", "none", "none")), 2);
+    assert_eq!(count(symmetry::Symmetry::Full, faces("none", "none", "pipe", "pipe", "none", "none")), 3);
+    assert_eq!(count(symmetry::Symmetry::Full, faces("none", "none", "none", "none", "none", "none")), 1);
+
+    let built = pipe_tileset().build().unwrap();
+    assert_eq!(built.variants.len(), 16);
+    assert!(built.variants.iter().all(|variant| variant.id != rules::EMPTY && variant.id != rules::BORDER));
+    let size:usize = 8;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let solution = weighted::Weighted::try_solve_weighted(&map, &built.rules, &built.weights, weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(8)).unwrap();
+    assert!(validate(&solution, &built.rules));
+}
+
+#[test]
+fn test_many_tiles(){
+    //tiles in the same layer modulo 100 can touch, BORDER accepts everything
+    let tiles: u16 = 600;
+    let rules: Vec<DirectionMapping<HashSet<u16>>> = (0..tiles).map(|tile| {
+        let allowed: HashSet<u16> = if tile == rules::BORDER as u16
+        {
+            (0..tiles).collect()
+        }
+        else
+        {
+            (0..tiles).filter(|&other| other % 100 == tile % 100 && other != rules::BORDER as u16).collect()
+        };
+        DirectionMapping::from_fn(|_| allowed.clone())
+    }).collect();
+    let size:usize = 6;
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY as u16, rules::BORDER as u16);
+    map.set(2, 2, 2, 542);
+    let solution = queueprop_dynset::QueuePropDynSet::try_solve_tiles(&map, &rules, &mut StdRng::seed_from_u64(9)).unwrap();
+    let mut high = false;
+    for (x, y, z) in PosIter3D::new_no_border(&solution)
+    {
+        let tile = solution.get(x, y, z);
+        assert_eq!(tile % 100, 42);
+        high |= tile > u8::MAX as u16;
+    }
+    assert!(high);
+    //weights are checked like for the weighted solver
+    let short = queueprop_dynset::QueuePropDynSet::try_solve_tiles_weighted(&map, &rules, &[1.0; 10], weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(9));
+    assert!(matches!(short, Err(weighted::WeightedError::MissingWeights{tiles: 600, weights: 10})));
+
+    //random sample has more patterns than fit into u8
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut sample = Vec3D::new(8, 8, 8, 0u8);
+    for (x, y, z) in PosIter3D::new(&sample)
+    {
+        sample.set(x, y, z, rand::Rng::gen_range(&mut rng, 0..4));
+    }
+    let model = overlapping::OverlappingModel::new(&sample, 2, symmetry::Symmetry::None).unwrap();
+    assert!(model.patterns().len() > u8::MAX as usize);
+}
+
+#[test]
+fn test_rules_check(){
+    assert!(rules_check::check(&rules::get_pipes_rules()).is_empty());
+
+    let none = || HashSet::<u8>::new();
+    let mut rules: Vec<DirectionMapping<HashSet<u8>>> = (0..=u8::MAX).map(|_| DirectionMapping::from_fn(|_| none())).collect();
+    let all = HashSet::from([1, 2]);
+    rules[1] = DirectionMapping::from_fn(|_| all.clone());
+    //tile 2 forgets that 1 may be above it and references tile 9 which has no rules
+    rules[2] = DirectionMapping::new(HashSet::from([2, 9]), all.clone(), all.clone(), all.clone(), all.clone(), all.clone());
+    //tile 5 only accepts itself on the right, nothing else accepts it
+    rules[5] = DirectionMapping::new(all.clone(), all.clone(), HashSet::from([5]), none(), all.clone(), all.clone());
+    let issues = rules_check::check(&rules);
+    assert!(issues.contains(&rules_check::RuleIssue::Asymmetric{tile: 1, direction: 1, other: 2}));
+    assert!(issues.contains(&rules_check::RuleIssue::UnknownTile{tile: 2, direction: 0, other: 9}));
+    assert!(issues.contains(&rules_check::RuleIssue::EmptyDirection{tile: 5, direction: 3}));
+    assert!(issues.contains(&rules_check::RuleIssue::Unplaceable{tile: 5}));
+    assert!(!issues.contains(&rules_check::RuleIssue::Unplaceable{tile: 1}));
+    assert_eq!(issues.iter().filter(|issue| matches!(issue, rules_check::RuleIssue::Unplaceable{..})).count(), 1);
+}
+
+//every pair of cells which are neighbors through the boundaries is allowed by rules
+fn validate_bounded(map: &Vec3D<u8>, rules: &Vec<DirectionMapping<HashSet<u8>>>, boundaries: boundary::Boundaries) -> bool
+{
+    PosIter3D::new(map).all(|(x, y, z)| {
+        let tile = map.get(x, y, z);
+        boundaries.neighbors((map.x_size, map.y_size, map.z_size), (x, y, z)).iter().zip(vec3d::CONSTRAINING_DIRECTION).all(|(neighbor, direction)| match *neighbor {
+            boundary::Neighbor::Cell((nx, ny, nz)) => rules[map.get(nx, ny, nz) as usize].get(direction).contains(&tile),
+            boundary::Neighbor::Border => rules[rules::BORDER as usize].get(direction).contains(&tile),
+            boundary::Neighbor::Outside => true
+        })
+    })
+}
+
+#[test]
+fn test_boundaries(){
+    let size:usize = 6;
+    let map = Vec3D::new(size, size, size, rules::EMPTY);
+    let rules = pipe_segments_rules();
+    let periodic = boundary::Boundaries::all(boundary::Boundary::Periodic);
+    let wrapping = boundary::Boundaries::new(boundary::Boundary::Periodic, boundary::Boundary::Fixed, boundary::Boundary::Open);
+    for boundaries in [periodic, wrapping]
+    {
+        let solution = (0..20)
+            .find_map(|seed| bounded::Bounded::try_solve_bounded(&map, &rules, boundaries, &mut StdRng::seed_from_u64(seed)).ok())
+            .unwrap();
+        assert!(validate_bounded(&solution, &rules, boundaries));
+        assert!(PosIter3D::new(&solution).all(|(x, y, z)| solution.get(x, y, z) != rules::BORDER));
+    }
+    //pipe leaving the grid on the right comes back on the left
+    let mut map = Vec3D::new(size, size, size, rules::EMPTY);
+    map.set(0, 2, 2, 0b0011_0000);
+    let solution = bounded::Bounded::try_solve_bounded(&map, &rules::get_pipes_rules(), periodic, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(validate_bounded(&solution, &rules::get_pipes_rules(), periodic));
+    assert!(PosIter3D::new(&solution).all(|(x, y, z)| solution.get(x, y, z) != rules::BORDER));
+    assert_ne!(solution.get(size - 1, 2, 2) & 0b0011_0000, 0);
+}
+
+fn validate_2d(map: &Vec2D<u8>, rules: &Vec<DirectionMapping2D<HashSet<u8>>>) -> bool
+{
+    PosIter2D::new_no_border(map).all(|(x, y)| {
+        let tile = map.get(x, y);
+        rules[map.get(x, y + 1) as usize].down().contains(&tile)
+            && rules[map.get(x, y - 1) as usize].up().contains(&tile)
+            && rules[map.get(x - 1, y) as usize].left().contains(&tile)
+            && rules[map.get(x + 1, y) as usize].right().contains(&tile)
+    })
+}
+
+#[test]
+fn test_2d(){
+    let size:usize = 24;
+    let map = Vec2D::with_borders(size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules_2d();
+    let solution = queueprop_bitarrayset::QueuePropBitArraySet2D::solve_seeded(&map, &rules, 42);
+    assert!(validate_2d(&solution, &rules));
+    assert_eq!(solution, queueprop_bitarrayset::QueuePropBitArraySet2D::solve_seeded(&map, &rules, 42));
+    assert_ne!(solution, queueprop_bitarrayset::QueuePropBitArraySet2D::solve_seeded(&map, &rules, 43));
+    for (x, y) in PosIter2D::new_no_border(&solution)
+    {
+        //no pipe connects front or back
+        assert!(solution.get(x, y) == rules::BORDER || solution.get(x, y) & 0b0000_1100 == 0);
+    }
+
+    //pipe going up below pipe going down leaves nothing legal in between
+    let mut map = Vec2D::with_borders(5, 5, rules::EMPTY, rules::BORDER);
+    map.set(2, 1, 0b1000_0000);
+    map.set(2, 3, 0b0100_0001);
+    let mut rules = rules;
+    //pipe rules accept BORDER everywhere, forbid it above the lower pipe
+    rules[0b1000_0000].get_mut(0).remove(&rules::BORDER);
+    let contradiction = queueprop_bitarrayset::QueuePropBitArraySet2D::try_solve(&map, &rules).unwrap_err();
+    assert_eq!(contradiction.position, (2, 2));
+    assert_eq!(contradiction.partial.get(2, 2), rules::EMPTY);
+}
+
+#[test]
+fn test_graph(){
+    //grid topology solves the same problems as solvers on Vec3D
+    let size:usize = 8;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let problem: Vec<u8> = PosIter3D::new(&map).map(|(x, y, z)| map.get(x, y, z)).collect();
+    let cells = graph::GraphWFC::try_solve_seeded(&topology::Topology::grid(size, size, size), &problem, &topology::rules_from_mapping(&rules), 42).unwrap();
+    let mut solution = map.clone();
+    for ((x, y, z), tile) in PosIter3D::new(&map).zip(cells)
+    {
+        solution.set(x, y, z, tile);
+    }
+    assert!(validate(&solution, &rules));
+
+    //every row of a sudoku, column and box is a clique of cells which differ, digits are tiles 10 to 18
+    let full = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+    let digits: Vec<u8> = full.bytes().map(|digit| digit - b'0' + 9).collect();
+    let mut sudoku = topology::Topology::new(81, 1);
+    for i in 0..9
+    {
+        sudoku.add_clique(&(0..9).map(|j| i * 9 + j).collect::<Vec<usize>>(), 0);
+        sudoku.add_clique(&(0..9).map(|j| j * 9 + i).collect::<Vec<usize>>(), 0);
+        sudoku.add_clique(&(0..9).map(|j| (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3).collect::<Vec<usize>>(), 0);
+    }
+    let mut differ: Vec<Vec<HashSet<u8>>> = vec![vec![HashSet::new()]; u8::MAX as usize + 1];
+    for digit in 10..19
+    {
+        differ[digit as usize][0] = (10..19).filter(|&other| other != digit).collect();
+    }
+    let problem: Vec<u8> = digits.iter().enumerate().map(|(cell, &digit)| if cell % 4 == 0 { rules::EMPTY } else { digit }).collect();
+    assert_eq!(graph::GraphWFC::try_solve_seeded(&sudoku, &problem, &differ, 1).unwrap(), digits);
+    let mut broken = problem.clone();
+    broken[0] = digits[1];
+    let contradiction = graph::GraphWFC::try_solve_seeded(&sudoku, &broken, &differ, 1).unwrap_err();
+    assert_eq!(contradiction.partial[contradiction.position], rules::EMPTY);
+
+    //hexagons with equal left and right neighbors make uniform rows
+    let (x_size, y_size) = (7, 6);
+    let mut rows: Vec<Vec<HashSet<u8>>> = vec![vec![HashSet::new(); 6]; u8::MAX as usize + 1];
+    for tile in [0, 1]
+    {
+        rows[tile as usize] = (0..6).map(|label| if label < 2 { HashSet::from([tile]) } else { HashSet::from([0, 1]) }).collect();
+    }
+    let cells = graph::GraphWFC::try_solve_seeded(&topology::Topology::hex(x_size, y_size), &vec![rules::EMPTY; x_size * y_size], &rows, 5).unwrap();
+    for row in cells.chunks(x_size)
+    {
+        assert!(row.iter().all(|&tile| tile == row[0]));
+    }
+    //tile without rules is rejected instead of being looked up
+    let mut problem = vec![rules::EMPTY; x_size * y_size];
+    problem[3] = 5;
+    let contradiction = graph::GraphWFC::try_solve_seeded(&topology::Topology::hex(x_size, y_size), &problem, &rows[..2], 5).unwrap_err();
+    assert_eq!((contradiction.position, contradiction.neighbor, contradiction.step), (3, 3, 0));
+    assert_eq!(contradiction.partial, vec![rules::EMPTY; x_size * y_size]);
+    assert_eq!(topology::Topology::moore(3, 3, 3).outgoing(13).len(), 26);
+    assert_eq!(topology::Topology::hex(3, 3).outgoing(4).len(), 6);
+}
+
+fn generate_world(order: &[(i64, i64, i64)], chunk_size: usize) -> Vec3D<u8>
+{
+    let mut generator = chunks::ChunkGenerator::new(&rules::get_pipes_rules(), (chunk_size, chunk_size, chunk_size), 77);
+    for &coords in order
+    {
+        generator.generate(coords).unwrap();
+    }
+    //chunks from -1 to 0 on every axis surrounded by BORDER
+    let size = 2 * chunk_size + 2;
+    let mut world = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    for (x, y, z) in PosIter3D::new_no_border(&world)
+    {
+        let position = (x as i64 - 1 - chunk_size as i64, y as i64 - 1 - chunk_size as i64, z as i64 - 1 - chunk_size as i64);
+        world.set(x, y, z, generator.tile(position).unwrap());
+    }
+    world
+}
+
+#[test]
+fn test_chunks(){
+    let chunk_size:usize = 5;
+    let mut order: Vec<(i64, i64, i64)> = Vec::new();
+    for x in -1..=0
+    {
+        for y in -1..=0
+        {
+            for z in -1..=0
+            {
+                order.push((x, y, z));
+            }
+        }
+    }
+    let rules = rules::get_pipes_rules();
+    let world = generate_world(&order, chunk_size);
+    assert!(validate(&world, &rules));
+    assert_eq!(world, generate_world(&order, chunk_size));
+    order.reverse();
+    assert!(validate(&generate_world(&order, chunk_size), &rules));
+
+    let generator = chunks::ChunkGenerator::new(&rules, (chunk_size, chunk_size, chunk_size), 77);
+    assert_eq!(generator.chunk_seed((3, -2, 1), 0), chunks::ChunkGenerator::new(&rules, (2, 2, 2), 77).chunk_seed((3, -2, 1), 0));
+    assert_ne!(generator.chunk_seed((3, -2, 1), 0), generator.chunk_seed((-2, 3, 1), 0));
+    assert_eq!(generator.tile((0, 0, 0)), None);
+}
+
+#[test]
+fn test_candidates(){
+    let size:usize = 8;
+    let rules = rules::get_pipes_rules();
+    let no_border: HashSet<u8> = rules::get_any_tile().into_iter().filter(|&tile| tile != rules::BORDER).collect();
+    let red: HashSet<u8> = no_border.iter().cloned().filter(|&tile| tile & 0b0000_0011 == 0).collect();
+    let mut candidates = Vec3D::with_borders(size, size, size, no_border, HashSet::from([rules::BORDER]));
+    let in_region = |x: usize, y: usize, z: usize| x < 4 && y < 4 && z < 4;
+    for (x, y, z) in PosIter3D::new_no_border(&candidates)
+    {
+        if in_region(x, y, z)
+        {
+            candidates.set(x, y, z, red.clone());
+        }
+    }
+    let solution = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_candidates(&candidates, &rules, &mut StdRng::seed_from_u64(4)).unwrap();
+    assert!(validate(&solution, &rules));
+    for (x, y, z) in PosIter3D::new_no_border(&solution)
+    {
+        assert!(candidates.get(x, y, z).contains(&solution.get(x, y, z)));
+    }
+
+    candidates.set(5, 5, 5, HashSet::new());
+    let contradiction = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_candidates(&candidates, &rules, &mut StdRng::seed_from_u64(4)).unwrap_err();
+    assert_eq!(contradiction.position, (5, 5, 5));
+}
+
+#[test]
+fn test_ac4(){
+    //support counting reaches the same domains as recomputing them, so same choices give same solution
+    let size:usize = 6;
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    map.set(2, 2, 2, 0b1001_0001);
+    for rules in [rules::get_pipes_rules(), pipe_segments_rules()]
+    {
+        for seed in 0..3
+        {
+            let expected = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_seeded(&map, &rules, seed);
+            let solution = ac4::AC4::try_solve_seeded(&map, &rules, seed);
+            match (expected, solution) {
+                (Ok(expected), Ok(solution)) => {
+                    assert!(validate(&solution, &rules));
+                    assert_eq!(solution, expected);
+                },
+                (Err(expected), Err(contradiction)) => assert_eq!(contradiction.step, expected.step),
+                _ => panic!("solvers disagree for seed {}", seed)
+            }
+        }
+    }
+
+    //every tile supported by all 256 tiles of each neighbor
+    let all: HashSet<u8> = (0..=u8::MAX).collect();
+    let rules = vec![DirectionMapping::new(all.clone(), all.clone(), all.clone(), all.clone(), all.clone(), all); 256];
+    let map = Vec3D::with_borders(4, 4, 4, rules::EMPTY, rules::BORDER);
+    assert!(validate(&ac4::AC4::solve_seeded(&map, &rules, 42), &rules));
+
+    //fixed tile which no rule allows is rejected instead of being looked up
+    let rules = rules::get_pipes_rules();
+    let mut map = Vec3D::with_borders(5, 5, 5, rules::EMPTY, rules::BORDER);
+    map.set(2, 2, 2, 0b1111_0011);
+    let contradiction = ac4::AC4::try_solve_seeded(&map, &rules, 42).unwrap_err();
+    assert_eq!((contradiction.position, contradiction.step), ((2, 2, 2), 0));
+    let contradiction = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_seeded(&map, &rules, 42).unwrap_err();
+    assert_eq!((contradiction.position, contradiction.step), ((2, 2, 2), 0));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel(){
+    use wfc::wfc::parallel::Parallel;
+    let size:usize = 8;
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    map.set(3, 3, 3, 0b1001_0001);
+    let rules = pipe_segments_rules();
+    //result is the first attempt which succeeds one after another, whatever the number of threads
+    let sequential = (0..64u64).find_map(|attempt| queueprop_bitarrayset::QueuePropBitArraySet::try_solve_seeded(&map, &rules, 100 + attempt).ok()).unwrap();
+    for threads in [1, 4]
+    {
+        let solution = Parallel::<queueprop_bitarrayset::QueuePropBitArraySet>::try_solve_restarts(&map, &rules, 100, 64, threads).unwrap();
+        assert!(validate(&solution, &rules));
+        assert_eq!(solution, sequential);
+    }
+    //zero attempts still makes one
+    let single = Parallel::<queueprop_bitarrayset::QueuePropBitArraySet>::try_solve_restarts(&map, &rules, 100, 0, 4);
+    assert_eq!(single.is_ok(), queueprop_bitarrayset::QueuePropBitArraySet::try_solve_seeded(&map, &rules, 100).is_ok());
+}
+
+#[test]
+fn test_budget(){
+    let size:usize = 10;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let solve = |budget: &budget::Budget| queueprop_bitarrayset::QueuePropBitArraySet::try_solve_with_budget(&map, &rules, budget, &mut StdRng::seed_from_u64(6));
+    let stopped = |budget: budget::Budget| match solve(&budget) {
+        Err(budget::SolveError::Stopped{reason, step, partial}) => (reason, step, partial),
+        _ => panic!("budget {:?} was not exhausted", budget)
+    };
+
+    assert_eq!(solve(&budget::Budget::new()).unwrap(), queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&map, &rules, 6));
+    let (reason, step, partial) = stopped(budget::Budget::new().max_collapses(10));
+    assert_eq!((reason, step), (budget::StopReason::CollapsesExceeded, 10));
+    //partial result is a map which can be finished later
+    assert!(PosIter3D::new_no_border(&partial).any(|(x, y, z)| partial.get(x, y, z) == rules::EMPTY));
+    assert!(validate(&queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&partial, &rules, 6), &rules));
+    assert_eq!(stopped(budget::Budget::new().max_propagations(100)).0, budget::StopReason::PropagationsExceeded);
+    assert_eq!(stopped(budget::Budget::new().max_time(std::time::Duration::ZERO)).0, budget::StopReason::TimeExceeded);
+
+    //token cancelled from another thread stops the solve before the first collapse
+    let cancel = budget::CancelToken::new();
+    let canceller = cancel.clone();
+    std::thread::spawn(move || canceller.cancel()).join().unwrap();
+    let (reason, step, partial) = stopped(budget::Budget::new().cancel(cancel));
+    assert_eq!((reason, step), (budget::StopReason::Cancelled, 0));
+    assert_eq!(partial, map);
+
+    check_solvers!(check_budget());
+}
+
+fn check_budget<T: WFC>()
+{
+    let map = Vec3D::with_borders(4, 4, 4, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let solve = |budget: budget::Budget| T::try_solve_with_budget(&map, &rules, &budget, &mut StdRng::seed_from_u64(5));
+    assert_eq!(solve(budget::Budget::new()).unwrap(), T::solve_seeded(&map, &rules, 5));
+    let cancel = budget::CancelToken::new();
+    cancel.cancel();
+    assert!(matches!(solve(budget::Budget::new().cancel(cancel)), Err(budget::SolveError::Stopped{reason: budget::StopReason::Cancelled, step: 0, ..})));
+    match solve(budget::Budget::new().max_collapses(3)) {
+        Err(budget::SolveError::Stopped{reason: budget::StopReason::CollapsesExceeded, step: 3, partial}) => {
+            assert!(PosIter3D::new_no_border(&partial).any(|(x, y, z)| partial.get(x, y, z) == rules::EMPTY));
+        },
+        _ => panic!("collapses of {} were not limited", std::any::type_name::<T>())
+    }
+}
+
+#[test]
+fn test_snapshot(){
+    let size:usize = 8;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let mut uninterrupted = stepper::Stepper::new(&map, &rules, snapshot::ResumableRng::new(21));
+    uninterrupted.by_ref().for_each(drop);
+
+    let mut stepper = stepper::Stepper::new(&map, &rules, snapshot::ResumableRng::new(21));
+    stepper.by_ref().take(40).for_each(drop);
+    let bytes = stepper.snapshot();
+    let mut resumed = stepper::Stepper::restore(&bytes).unwrap();
+    assert_eq!(resumed.steps(), stepper.steps());
+    assert_eq!(resumed.solution(), stepper.solution());
+    resumed.by_ref().for_each(drop);
+    assert!(resumed.is_finished());
+    assert_eq!(resumed.solution(), uninterrupted.solution());
+    assert!(validate(&resumed.solution(), &rules));
+
+    assert_eq!(stepper::Stepper::restore(&bytes[..bytes.len() - 1]).err(), Some(snapshot::SnapshotError::Truncated));
+    assert_eq!(stepper::Stepper::restore(b"nope").err(), Some(snapshot::SnapshotError::BadMagic));
+    //crafted snapshots fail to restore instead of panicking later, rules of 8 tiles start after magic, version, step, flag, rng and length
+    let small_rules: Vec<DirectionMapping<HashSet<u8>>> = (0..8).map(|_| DirectionMapping::from_fn(|_| HashSet::from([0, 1]))).collect();
+    let small = stepper::Stepper::new(&Vec3D::with_borders(4, 4, 4, rules::EMPTY, rules::BORDER), &small_rules, snapshot::ResumableRng::new(21)).snapshot();
+    let rules_start = 4 + 1 + 8 + 1 + 24 + 8;
+    let mut bytes = small.clone();
+    bytes[rules_start + 25] = 1;
+    assert_eq!(stepper::Stepper::restore(&bytes).err(), Some(snapshot::SnapshotError::Invalid("tile without rules in rules")));
+    let mut bytes = small;
+    bytes[rules_start + 8 * 6 * 32 + 24] = 0b11;
+    assert_eq!(stepper::Stepper::restore(&bytes).err(), Some(snapshot::SnapshotError::Invalid("border cell with several candidates")));
+
+    //rng gives the same numbers as StdRng and restores from any position, also inside a u64
+    let mut rng = snapshot::ResumableRng::new(21);
+    let mut std_rng = StdRng::seed_from_u64(21);
+    assert_eq!(rng.next_u32(), std_rng.next_u32());
+    let mut restored = snapshot::ResumableRng::restore(rng.seed(), rng.word_pos());
+    for _ in 0..100
+    {
+        let expected = std_rng.next_u64();
+        assert_eq!(rng.next_u64(), expected);
+        assert_eq!(restored.next_u64(), expected);
+    }
+}
+
+#[test]
+fn test_regenerate(){
+    let size:usize = 10;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let solution = queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&map, &rules, 3);
+    let region = queueprop_bitarrayset::QueuePropBitArraySet::box_region(&solution, (3, 3, 3), (7, 7, 7));
+    let regenerated = queueprop_bitarrayset::QueuePropBitArraySet::try_regenerate(&solution, &region, &rules, &mut StdRng::seed_from_u64(4)).unwrap();
+    assert!(validate(&regenerated, &rules));
+    for (x, y, z) in PosIter3D::new(&solution)
+    {
+        if !region.get(x, y, z)
+        {
+            assert_eq!(regenerated.get(x, y, z), solution.get(x, y, z));
+        }
+    }
+    assert_ne!(regenerated, solution);
+}
+
+#[test]
+fn test_counts(){
+    let size:usize = 6;
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let rules = rules::get_pipes_rules();
+    let tiles: Vec<u8> = rules_check::tiles(&rules).into_iter().filter(|&tile| tile != rules::BORDER).collect();
+    let single: HashSet<u8> = HashSet::from([tiles[1]]);
+    let rare: HashSet<u8> = tiles[2..6].iter().copied().collect();
+    let common: HashSet<u8> = HashSet::from([tiles[0]]);
+    let constraints = vec![
+        counts::CountConstraint::exactly(single.clone(), 1),
+        counts::CountConstraint::at_most(rare.clone(), 5),
+        counts::CountConstraint::at_least(common.clone(), 10)
+    ];
+    let count = |solution: &Vec3D<u8>, group: &HashSet<u8>| PosIter3D::new_no_border(solution).filter(|&(x, y, z)| group.contains(&solution.get(x, y, z))).count();
+    //rare tiles left by the seeds, common ones are held right at their minimum
+    for (seed, rare_count) in [(0, 1), (1, 1), (2, 0), (3, 1)]
+    {
+        let solution = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_with_counts(&map, &rules, &constraints, &mut StdRng::seed_from_u64(seed)).unwrap();
+        assert!(validate(&solution, &rules));
+        assert_eq!(count(&solution, &single), 1);
+        assert_eq!(count(&solution, &rare), rare_count);
+        assert_eq!(count(&solution, &common), 10);
+    }
+    let impossible = vec![counts::CountConstraint::at_least(single, size.pow(3))];
+    let error = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_with_counts(&map, &rules, &impossible, &mut StdRng::seed_from_u64(0)).unwrap_err();
+    assert!(matches!(error, budget::SolveError::Unsatisfiable{step: 0, ..}));
+}
+
+//cells linked with start through member tiles of connectivity
+fn component(solution: &Vec3D<u8>, connectivity: &connectivity::Connectivity, start: (usize, usize, usize)) -> HashSet<(usize, usize, usize)>
+{
+    let mut seen = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some((x, y, z)) = stack.pop()
+    {
+        let tile = solution.get(x, y, z);
+        for (direction, (dx, dy, dz)) in wfc::wfc::direction_mapping::OFFSETS.iter().enumerate()
+        {
+            let next = ((x as isize + dx) as usize, (y as isize + dy) as usize, (z as isize + dz) as usize);
+            let other = solution.get(next.0, next.1, next.2);
+            if !solution.is_border(next.0, next.1, next.2) && connectivity.members.contains(&other)
+                && *connectivity.open[tile as usize].get(direction) && *connectivity.open[other as usize].get(direction ^ 1)
+                && seen.insert(next)
+            {
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
+//number of connected components formed by member tiles of connectivity
+fn count_components(solution: &Vec3D<u8>, connectivity: &connectivity::Connectivity) -> usize
+{
+    let mut seen = HashSet::new();
+    let mut components = 0;
+    for start in PosIter3D::new_no_border(solution)
+    {
+        if !seen.contains(&start) && connectivity.members.contains(&solution.get(start.0, start.1, start.2))
+        {
+            components += 1;
+            seen.extend(component(solution, connectivity, start));
+        }
+    }
+    components
+}
+
+#[test]
+fn test_connectivity(){
+    let size:usize = 6;
+    let rules = rules::get_pipes_rules();
+    let connectivity = connectivity::get_pipes_connectivity(rules::RED);
+    let plain = queueprop_bitarrayset::QueuePropBitArraySet::solve_seeded(&Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER), &rules, 0);
+    assert!(count_components(&plain, &connectivity) > 1);
+    //red pipe ends at opposite corners
+    let mut map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    map.set(1, 1, 1, 0b1000_0000 | rules::RED);
+    map.set(size - 2, size - 2, size - 2, 0b0100_0000 | rules::RED);
+    for seed in 0..4
+    {
+        let solution = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_connected(&map, &rules, &connectivity, &mut StdRng::seed_from_u64(seed)).unwrap();
+        assert!(validate(&solution, &rules));
+        assert_eq!(count_components(&solution, &connectivity), 1);
+        assert_eq!(solution.get(1, 1, 1), 0b1000_0000 | rules::RED);
+    }
+}
+
+#[test]
+fn test_path(){
+    let size:usize = 6;
+    let rules = rules::get_pipes_rules();
+    let connectivity = connectivity::get_pipes_connectivity(rules::BLUE);
+    let map = Vec3D::with_borders(size, size, size, rules::EMPTY, rules::BORDER);
+    let endpoints = [(1, 1, 1), (4, 4, 4), (1, 4, 2)];
+    for seed in 0..4
+    {
+        let solution = queueprop_bitarrayset::QueuePropBitArraySet::try_solve_path(&map, &rules, &connectivity, &endpoints, &mut StdRng::seed_from_u64(seed)).unwrap();
+        assert!(validate(&solution, &rules));
+        let linked = component(&solution, &connectivity, endpoints[0]);
+        assert!(endpoints.iter().all(|endpoint| linked.contains(endpoint)));
+    }
+}
+
+#[test]
+fn test_tileset_toml(){
+    let built = tileset_toml::load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tilesets/pipes.toml")).unwrap();
+    let expected = pipe_tileset().build().unwrap();
+    assert_eq!(built.variants.len(), expected.variants.len());
+    for (tile, rules) in built.rules.iter().enumerate()
+    {
+        assert!((0..6).all(|direction| rules.get(direction) == expected.rules[tile].get(direction)));
+    }
+    assert_eq!(built.weights, expected.weights);
+    assert_eq!(built.names.values().filter(|name| *name == "straight").count(), 3);
+    assert_eq!(built.names[&built.variants[0].id], "empty");
+    let map = Vec3D::with_borders(6, 6, 6, rules::EMPTY, rules::BORDER);
+    let solution = weighted::Weighted::try_solve_weighted(&map, &built.rules, &built.weights, weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(3)).unwrap();
+    assert!(validate(&solution, &built.rules));
+
+    let sockets = r#"sockets = { up = "a", down = "a", right = "a", left = "a", front = "a", back = "a" }"#;
+    let error = |text: String| tileset_toml::load(&text).err().unwrap();
+    assert!(matches!(error(format!("[[tile]]\nname = \"a\"\n{}\n[[tile]]\nname = \"a\"\n{}", sockets, sockets)), tileset_toml::FormatError::DuplicateTile(_)));
+    assert!(matches!(error(format!("[[tile]]\nname = \"a\"\nsymmetry = \"twisted\"\n{}", sockets)), tileset_toml::FormatError::UnknownSymmetry{..}));
+    assert!(matches!(error(format!("[[tile]]\nname = \"a\"\nweight = -1.0\n{}", sockets)), tileset_toml::FormatError::InvalidWeight{..}));
+    assert!(matches!(error("[[tile]]\nname = \"a\"\nsockets = { up = \"a\" }".to_string()), tileset_toml::FormatError::Parse(_)));
+    assert!(matches!(error(format!("[[tile]]\nname = \"a\"\ncolour = \"red\"\n{}", sockets)), tileset_toml::FormatError::Parse(_)));
+    assert!(matches!(tileset_toml::load_file("missing.toml"), Err(tileset_toml::FormatError::Io(_))));
+}
+
+#[test]
+fn test_tileset_xml(){
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tilesets/lines.xml");
+    let tiles = tileset_xml::load_file(path, None).unwrap();
+    let ids: HashMap<&str, u8> = tiles.names.iter().map(|(&id, name)| (name.as_str(), id)).collect();
+    assert_eq!(ids.len(), 4);
+    assert_eq!(tiles.weights[ids["line 1"] as usize], 2.0);
+    //pipe on top, bottom, left and right side of each variant as drawn in image rows
+    let sides = HashMap::from([("empty 0", [false; 4]), ("cross 0", [true; 4]), ("line 0", [false, false, true, true]), ("line 1", [true, true, false, false])]);
+    for (a, sides_a) in &sides
+    {
+        for (b, sides_b) in &sides
+        {
+            let rules = &tiles.rules[ids[a] as usize];
+            assert_eq!(rules.up().contains(&ids[b]), sides_a[1] == sides_b[0]);
+            assert_eq!(rules.down().contains(&ids[b]), sides_a[0] == sides_b[1]);
+            assert_eq!(rules.right().contains(&ids[b]), sides_a[2] == sides_b[3]);
+            assert_eq!(rules.left().contains(&ids[b]), sides_a[3] == sides_b[2]);
+        }
+    }
+    //BORDER accepts every tile without being listed by them
+    let only_border = |rules| rules_check::check(rules).iter().all(|issue| matches!(issue, rules_check::RuleIssue::Asymmetric{tile: rules::BORDER, ..}));
+    assert!(only_border(&tiles.rules));
+    let map = Vec3D::with_borders(12, 12, 3, rules::EMPTY, rules::BORDER);
+    let solution = weighted::Weighted::try_solve_weighted(&map, &tiles.rules, &tiles.weights, weighted::CellSelection::Entropy, &mut StdRng::seed_from_u64(5)).unwrap();
+    assert!(validate(&solution, &tiles.rules));
+
+    let plain = tileset_xml::load_file(path, Some("plain")).unwrap();
+    assert!(plain.names.values().all(|name| !name.starts_with("cross")));
+    assert_eq!(plain.names.len(), 3);
+    assert!(only_border(&plain.rules));
+
+    let letters = "<set><tiles><tile name=\"x\"/><tile name=\"i\" symmetry=\"I\"/><tile name=\"d\" symmetry=\"\\\"/>\
+        <tile name=\"t\" symmetry=\"T\"/><tile name=\"l\" symmetry=\"L\"/><tile name=\"f\" symmetry=\"F\"/></tiles></set>";
+    assert_eq!(tileset_xml::load(letters, None).unwrap().names.len(), 1 + 2 + 2 + 4 + 4 + 8);
+    let error = |text: &str| tileset_xml::load(text, None).err().unwrap();
+    assert!(matches!(error("<set><tiles><tile name=\"a\" symmetry=\"Q\"/></tiles></set>"), tileset_xml::XmlError::InvalidAttribute{..}));
+    assert!(matches!(error("<set><tiles><tile name=\"a\"/></tiles><neighbors><neighbor left=\"a\" right=\"b\"/></neighbors></set>"), tileset_xml::XmlError::UnknownTile(_)));
+    assert!(matches!(error("<set><tiles><tile name=\"a\"/><tile name=\"a\"/></tiles></set>"), tileset_xml::XmlError::DuplicateTile(_)));
+    assert!(matches!(error("<set><tiles><tile/></tiles></set>"), tileset_xml::XmlError::MissingAttribute{..}));
+    assert!(matches!(error("<set><tiles>"), tileset_xml::XmlError::Parse(_)));
+    assert!(matches!(tileset_xml::load_file(path, Some("missing")), Err(tileset_xml::XmlError::UnknownSubset(_))));
+}